@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[derive(Debug)]
 /// Server Configuration
 pub struct Config {
@@ -7,6 +9,13 @@ pub struct Config {
     pub server_port: u16,
     /// GDB command execution timeout in seconds
     pub command_timeout: u64,
+    /// Number of async and stream records kept per session
+    pub event_history_size: usize,
+    /// Most steps made by a repeated step unless asked for more
+    pub max_steps: usize,
+    /// Directory the MI transcripts of sessions are recorded to, recording
+    /// is off if not set
+    pub transcript_dir: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -21,6 +30,15 @@ impl Default for Config {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(10),
+            event_history_size: std::env::var("GDB_EVENT_HISTORY_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+            max_steps: std::env::var("GDB_MAX_STEPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+            transcript_dir: std::env::var_os("GDB_TRANSCRIPT_DIR").map(PathBuf::from),
         }
     }
 }
@@ -17,8 +17,18 @@ pub enum AppError {
     #[error("GDB quit")]
     GDBQuit,
 
-    #[error("Parse error: {0}")]
-    ParseError(String),
+    #[error("Parse error: {reason}, line: {line:?}")]
+    ParseError {
+        /// The raw line that could not be parsed
+        line: String,
+        reason: String,
+    },
+
+    #[error(
+        "Out of hardware breakpoint/watchpoint slots, delete or disable some hardware \
+         breakpoints and watchpoints first: {0}"
+    )]
+    HardwareSlotsExhausted(String),
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
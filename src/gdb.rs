@@ -1,9 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use tokio::sync::{Mutex, mpsc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, broadcast, mpsc, watch};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, warn};
 use uuid::Uuid;
@@ -11,30 +15,501 @@ use uuid::Uuid;
 use crate::TRANSPORT;
 use crate::config::Config;
 use crate::error::{AppError, AppResult};
-use crate::mi::commands::{BreakPointLocation, BreakPointNumber, MiCommand, RegisterFormat};
-use crate::mi::output::{OutOfBandRecord, ResultClass, ResultRecord};
+use crate::mi::commands::{
+    BreakPointLocation, BreakPointNumber, BreakPointOptions, CatchEvent, DisassembleMode,
+    MiCommand, RegisterFormat, ValueFormat, WatchMode,
+};
+use crate::mi::output::{
+    AsyncClass, OutOfBandRecord, ResultClass, ResultRecord, StreamKind, quoted_strings,
+};
 use crate::mi::{GDB, GDBBuilder};
 use crate::models::{
-    BreakPoint, GDBSession, GDBSessionStatus, Memory, Register, StackFrame, Variable,
+    AllBacktraces, AsyncEvent, BacktraceGroup, BreakPoint, BreakPointActions, BreakPointSet,
+    BreakPointSnapshot, BreakPointSpec, CollectedValue, EventHistory, ExecutionOutcome,
+    ExpressionValue, GDBSession, GDBSessionStatus, Instruction, Memory, Register,
+    RestoredBreakPoints, RustPanic, SessionEvent, SessionNotification, SnapshotStore, StackFrame,
+    StepCondition, StepEnd, StepRepeat, SteppingOutcome, StopEvent, StopReason, Thread, ThreadList,
+    ThreadState, Variable, Watchpoint,
 };
 
+/// Prefix of the output of logpoints, followed by their tag and `] `
+const LOGPOINT_MARKER: &str = "[logpoint:";
+
+/// Parse breakpoint numbers given as `major` or `major.minor`
+fn parse_breakpoint_numbers<S: AsRef<str>>(numbers: &[S]) -> AppResult<Vec<BreakPointNumber>> {
+    numbers
+        .iter()
+        .map(|number| {
+            let number = number.as_ref().trim();
+            number.parse().map_err(|_| {
+                AppError::InvalidArgument(format!("invalid breakpoint number {:?}", number))
+            })
+        })
+        .collect()
+}
+
+/// Parse the stop reasons to wait for, GDB's unknown to this server are
+/// rejected as they would never match
+fn parse_stop_reasons<S: AsRef<str>>(reasons: &[S]) -> AppResult<Vec<StopReason>> {
+    reasons
+        .iter()
+        .map(|reason| {
+            let reason = reason.as_ref().trim();
+            match reason.parse() {
+                Ok(StopReason::Other(_)) | Err(_) => {
+                    Err(AppError::InvalidArgument(format!("unknown stop reason {:?}", reason)))
+                }
+                Ok(reason) => Ok(reason),
+            }
+        })
+        .collect()
+}
+
+/// Messages of GDB meaning the target has no hardware breakpoint or
+/// watchpoint slot left
+const HARDWARE_SLOT_ERRORS: &[&str] = &[
+    "Could not insert hardware",
+    "too many hardware breakpoints/watchpoints",
+    "Hardware breakpoints used exceeds limit",
+    "Target can only support one kind of HW watchpoint at a time",
+];
+
+/// Error for a command GDB answered with `^error`
+fn command_error(results: &serde_json::Value) -> AppError {
+    let msg = results.get("msg").and_then(|msg| msg.as_str()).unwrap_or_default();
+    if HARDWARE_SLOT_ERRORS.iter().any(|pattern| msg.contains(pattern)) {
+        AppError::HardwareSlotsExhausted(msg.trim().to_string())
+    } else {
+        AppError::GDBError(results.to_string())
+    }
+}
+
+/// The catchpoint and the signal it caught, if `data` is the console output
+/// of a signal catchpoint, e.g. `Catchpoint 1 (signal SIGUSR1), `
+fn signal_catchpoint(data: &str) -> Option<(BreakPointNumber, String)> {
+    let (_, caught) = data.split_once("Catchpoint ")?;
+    let (number, caught) = caught.split_once(" (signal ")?;
+    let (signal, _) = caught.split_once(')')?;
+    Some((number.trim().parse().ok()?, signal.to_string()))
+}
+
+/// Add the catchpoint and signal printed on the console by a signal
+/// catchpoint to the stop record that follows, GDB leaves them out of it
+fn decode_signal_catchpoint(
+    record: &mut OutOfBandRecord,
+    caught: &mut Option<(BreakPointNumber, String)>,
+) {
+    match record {
+        OutOfBandRecord::StreamRecord { kind: StreamKind::Console, data } => {
+            if let Some(signal) = signal_catchpoint(data) {
+                *caught = Some(signal);
+            }
+        }
+        OutOfBandRecord::AsyncRecord { class: AsyncClass::Stopped, results, .. } => {
+            let Some((number, signal)) = caught.take() else { return };
+            if let Some(results) = results.as_object_mut()
+                && !results.contains_key("bkptno")
+            {
+                results.insert("reason".to_string(), json!("signal-received"));
+                results.insert("bkptno".to_string(), json!(number.to_string()));
+                results.insert("signal-name".to_string(), json!(signal));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Functions of the Rust runtime a panic goes through
+const RUST_RUNTIME_FUNCTIONS: &[&str] = &["std::", "core::", "alloc::", "rust_", "__rust"];
+
+/// Format string of a panic message from the literal parts of its
+/// `fmt::Arguments`, with `{}` for each of its `args` arguments. The
+/// arguments go between the parts, and after the last one if there are as
+/// many as parts.
+fn panic_message_template(pieces: &[String], args: Option<usize>) -> String {
+    let escaped: Vec<_> =
+        pieces.iter().map(|piece| piece.replace('{', "{{").replace('}', "}}")).collect();
+    let mut template = escaped.join("{}");
+    for _ in pieces.len().saturating_sub(1)..args.unwrap_or_default() {
+        template.push_str("{}");
+    }
+    template
+}
+
+/// Whether a value printed by GDB is true as C takes it: zero, false and
+/// null pointers are false, e.g. `0`, `0 '\000'` and `(int *) 0x0`
+fn is_true(value: &str) -> bool {
+    let value = value.trim();
+    let value = match value.strip_prefix('(').and_then(|value| value.split_once(") ")) {
+        Some((_, value)) => value,
+        None => value,
+    };
+    let value = value.split_whitespace().next().unwrap_or_default();
+    let zero = match value.strip_prefix("0x") {
+        Some(hex) => hex.chars().all(|c| c == '0'),
+        None => value.parse::<f64>().is_ok_and(|number| number == 0.0),
+    };
+    !(zero || value == "false")
+}
+
+/// Send a notification to the client, if one is connected
+async fn notify<T: Serialize>(method: &str, params: &T) {
+    let transport = TRANSPORT.lock().await;
+    if let Some(transport) = transport.as_ref() {
+        match serde_json::to_value(params) {
+            Ok(params) => {
+                if let Err(e) = transport.send_notification(method, Some(params)).await {
+                    error!("Failed to send {} notification: {:?}", method, e);
+                }
+            }
+            Err(e) => error!("Failed to serialize {} notification: {}", method, e),
+        }
+    } else {
+        debug!("No transport to notify");
+    }
+}
+
 /// GDB Session Manager
 #[derive(Default)]
 pub struct GDBManager {
     /// Configuration
     config: Config,
     /// Session mapping table
-    sessions: Mutex<HashMap<String, GDBSessionHandle>>,
+    sessions: Mutex<HashMap<String, Arc<GDBSessionHandle>>>,
 }
 
 /// GDB Session Handle
 struct GDBSessionHandle {
     /// Session information
-    info: GDBSession,
+    info: Arc<Mutex<GDBSession>>,
     /// GDB instance
     gdb: GDB,
     /// OOB handle
     oob_handle: JoinHandle<()>,
+    /// Stops of the session once handled, subscribe to wait for a stop
+    stops: broadcast::Sender<StopEvent>,
+    /// Number of stops reported by GDB and not handled yet
+    pending_stops: watch::Sender<usize>,
+    /// Timeout of the commands of the session
+    command_timeout: Duration,
+    /// Recent async and stream records of the session
+    history: Arc<Mutex<EventHistory>>,
+    /// Breakpoints of the session
+    breakpoints: Arc<Mutex<BreakPointSet>>,
+    /// Logpoints of the session by the tag marking their output
+    logpoints: Arc<Mutex<HashMap<String, BreakPointNumber>>>,
+    /// Actions run when a breakpoint is hit, by breakpoint number
+    actions: Mutex<HashMap<usize, BreakPointActions>>,
+    /// What the actions collected
+    snapshots: Mutex<SnapshotStore>,
+    /// Breakpoints catching Rust panics, by major number
+    panic_breakpoints: Mutex<HashSet<usize>>,
+    /// Held while a catchpoint is set with a CLI command
+    cli_catch: Mutex<()>,
+}
+
+impl GDBSessionHandle {
+    /// Send a command, failing if GDB doesn't answer within the command
+    /// timeout
+    async fn execute(self: &Arc<Self>, command: &MiCommand) -> AppResult<ResultRecord> {
+        match tokio::time::timeout(self.command_timeout, self.gdb.execute(command)).await {
+            Ok(Ok(record)) => {
+                self.info.lock().await.desynced = false;
+                debug!("GDB output: {}", record.results);
+                Ok(record)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => {
+                self.resync();
+                Err(AppError::GDBTimeout)
+            }
+        }
+    }
+
+    /// Check that a session still answers after one of its commands timed
+    /// out. GDB replies in order, so once the probe returns the late result
+    /// has been drained and discarded; if the probe times out as well the
+    /// session is flagged as desynced until GDB answers again.
+    fn resync(self: &Arc<Self>) {
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let synced = match tokio::time::timeout(
+                handle.command_timeout,
+                handle.gdb.execute(MiCommand::list_features()),
+            )
+            .await
+            {
+                Ok(Ok(_)) => true,
+                // The target is running, GDB cannot be probed right now
+                Ok(Err(AppError::GDBBusy)) => return,
+                Ok(Err(e)) => {
+                    warn!("Failed to resync session: {}", e);
+                    false
+                }
+                Err(_) => false,
+            };
+            let late = handle.gdb.abandoned_commands();
+            if late > 0 {
+                warn!("{} timed out command(s) still waiting for their result", late);
+            }
+            handle.info.lock().await.desynced = !synced;
+        });
+    }
+
+    /// Handle the stops of the session one at a time, in the order GDB
+    /// reports them, and publish those the actions don't resume. Every stop
+    /// is handled exactly once, whether a tool waits for it or not.
+    async fn handle_stops(self: Arc<Self>, mut stops: mpsc::UnboundedReceiver<StopEvent>) {
+        while let Some(mut stopped) = stops.recv().await {
+            if self.handle_stop(&mut stopped).await {
+                // No subscriber is not an error, nobody waits for a stop
+                let _ = self.stops.send(stopped);
+            }
+            self.pending_stops.send_modify(|pending| *pending -= 1);
+        }
+        debug!("Stop channel closed");
+    }
+
+    /// Tell catchpoint and panic stops and run the actions of the breakpoint
+    /// hit. Returns whether the stop is reported, it isn't if the actions
+    /// resumed the target.
+    async fn handle_stop(self: &Arc<Self>, stopped: &mut StopEvent) -> bool {
+        let Some(number) = stopped.breakpoint else { return true };
+        let major = BreakPointNumber { major: number.major, minor: None };
+        let breakpoint = self.breakpoints.lock().await.get(major).cloned();
+        stopped.catchpoint = breakpoint.and_then(|breakpoint| breakpoint.catch_type);
+        if self.panic_breakpoints.lock().await.contains(&number.major) {
+            stopped.panic = Some(self.rust_panic(stopped).await);
+        }
+
+        let Some(actions) = self.actions.lock().await.get(&number.major).cloned() else {
+            return true;
+        };
+        let snapshot = self.collect_snapshot(stopped, &actions).await;
+        self.snapshots.lock().await.push(number.major, snapshot);
+        if !actions.auto_continue {
+            return true;
+        }
+        let error = match self.execute(&MiCommand::exec_continue()).await {
+            Ok(response) if response.class == ResultClass::Error => {
+                command_error(&response.results)
+            }
+            Ok(_) => return false,
+            Err(e) => e,
+        };
+        warn!("Failed to continue at breakpoint {}, reporting the stop: {}", number, error);
+        true
+    }
+
+    /// Message and location of the panic the target stopped at. At
+    /// `rust_begin_unwind` they are read from its `PanicInfo` argument, the
+    /// location falls back to the innermost frame outside the Rust runtime.
+    async fn rust_panic(self: &Arc<Self>, stopped: &StopEvent) -> RustPanic {
+        let thread_id = stopped.thread_id.map(|thread| thread as usize);
+        // Printed in full, a variable object would only show {...} for slices
+        let evaluate = async |expression: &str| {
+            let command = MiCommand::data_evaluate_expression(expression, thread_id, Some(0));
+            match self.execute(&command).await {
+                Ok(response) if response.class == ResultClass::Done => {
+                    response.results.get("value").and_then(|value| value.as_str()).map(String::from)
+                }
+                _ => None,
+            }
+        };
+        // The literal parts of the message, the arguments can't be formatted
+        let pieces = evaluate("info.message.pieces").await.map(|pieces| quoted_strings(&pieces));
+        let args = evaluate("info.message.args.length").await.and_then(|args| args.parse().ok());
+        let (message, message_template) = match (pieces, args) {
+            (Some(pieces), Some(0)) => (Some(pieces.concat()), None),
+            (Some(pieces), args) => (None, Some(panic_message_template(&pieces, args))),
+            (None, _) => (None, None),
+        };
+        let file = evaluate("info.location.file").await.map(|file| quoted_strings(&file));
+        let mut panic = RustPanic {
+            message: message.filter(|message| !message.is_empty()),
+            message_template,
+            file: file.and_then(|file| file.into_iter().next()),
+            line: evaluate("info.location.line").await.and_then(|line| line.parse().ok()),
+            column: evaluate("info.location.col").await.and_then(|column| column.parse().ok()),
+        };
+
+        if panic.file.is_none() {
+            let frames = self.get_frames(None, None).await.unwrap_or_default();
+            let caller = frames.into_iter().find(|frame| {
+                !RUST_RUNTIME_FUNCTIONS.iter().any(|prefix| frame.function.starts_with(prefix))
+                    && frame.file.as_ref().is_some_and(|file| !file.starts_with("/rustc/"))
+            });
+            if let Some(caller) = caller {
+                panic.file = caller.file;
+                panic.line = caller.line;
+                panic.column = None;
+            }
+        }
+        panic
+    }
+
+    /// Run the actions of a breakpoint at a stop, failures are recorded in
+    /// the snapshot rather than failing the stop
+    async fn collect_snapshot(
+        self: &Arc<Self>,
+        stopped: &StopEvent,
+        actions: &BreakPointActions,
+    ) -> BreakPointSnapshot {
+        let thread_id = stopped.thread_id.map(|thread| thread as usize);
+        let mut values = Vec::new();
+        for expression in &actions.expressions {
+            let value = self.evaluate_expression(expression, thread_id, None, None);
+            let (value, error) = match value.await {
+                Ok(value) => (value.value, None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            values.push(CollectedValue { expression: expression.clone(), value, error });
+        }
+
+        let mut errors = Vec::new();
+        let registers = if actions.registers.is_empty() {
+            None
+        } else {
+            match self.get_registers(thread_id, Some(actions.registers.clone())).await {
+                Ok(registers) => Some(registers),
+                Err(e) => {
+                    errors.push(format!("registers: {}", e));
+                    None
+                }
+            }
+        };
+        let backtrace = if actions.backtrace_depth == 0 {
+            None
+        } else {
+            let command =
+                MiCommand::stack_list_frames(None, Some(0), Some(actions.backtrace_depth - 1));
+            match self.execute(&command).await.and_then(|response| {
+                let stack = response.results.get("stack").cloned().unwrap_or_default();
+                Ok(serde_json::from_value(stack)?)
+            }) {
+                Ok(frames) => Some(frames),
+                Err(e) => {
+                    errors.push(format!("backtrace: {}", e));
+                    None
+                }
+            }
+        };
+
+        BreakPointSnapshot {
+            hit: 0,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            thread_id: stopped.thread_id,
+            frame: stopped.frame.clone(),
+            values,
+            registers,
+            backtrace,
+            errors: (!errors.is_empty()).then_some(errors),
+        }
+    }
+
+    /// Get the innermost `depth` frames of the given thread, all by default
+    async fn get_frames(
+        self: &Arc<Self>,
+        thread_id: Option<usize>,
+        depth: Option<usize>,
+    ) -> AppResult<Vec<StackFrame>> {
+        let high = depth.map(|depth| depth.saturating_sub(1));
+        let command = MiCommand::stack_list_frames(thread_id, None, high);
+        let response = self.execute(&command).await?;
+        if response.class == ResultClass::Error {
+            return Err(AppError::GDBError(response.results.to_string()));
+        }
+
+        Ok(serde_json::from_value(
+            response
+                .results
+                .get("stack")
+                .ok_or(AppError::NotFound("stack not found".to_string()))?
+                .to_owned(),
+        )?)
+    }
+
+    /// Evaluate an expression in the given thread and frame, by default the
+    /// selected ones, and return its value formatted as `format` and its type
+    async fn evaluate_expression(
+        self: &Arc<Self>,
+        expression: &str,
+        thread_id: Option<usize>,
+        frame_id: Option<usize>,
+        format: Option<ValueFormat>,
+    ) -> AppResult<ExpressionValue> {
+        let command = MiCommand::var_create(None, expression, None, thread_id, frame_id);
+        let response = self.execute(&command).await?;
+        if response.class == ResultClass::Error {
+            return Err(AppError::GDBError(response.results.to_string()));
+        }
+        let mut value: ExpressionValue = serde_json::from_value(response.results)?;
+
+        let formatted = match format {
+            Some(format) if format != ValueFormat::Natural => {
+                let command = MiCommand::var_evaluate_expression(&value.name, Some(format));
+                Some(self.execute(&command).await)
+            }
+            _ => None,
+        };
+        // The variable object was only needed for this evaluation
+        let command = MiCommand::var_delete(&value.name, true);
+        if let Err(e) = self.execute(&command).await {
+            warn!("Failed to delete variable object {}: {}", value.name, e);
+        }
+
+        if let Some(response) = formatted {
+            let response = response?;
+            if response.class == ResultClass::Error {
+                return Err(AppError::GDBError(response.results.to_string()));
+            }
+            value.value = response.results.get("value").and_then(|v| v.as_str()).map(String::from);
+        }
+        Ok(value)
+    }
+
+    /// Get registers of the given thread, by default the selected one
+    async fn get_registers(
+        self: &Arc<Self>,
+        thread_id: Option<usize>,
+        reg_list: Option<Vec<String>>,
+    ) -> AppResult<Vec<Register>> {
+        let reg_list = reg_list
+            .map(|s| s.iter().map(|num| num.parse::<usize>()).collect::<Result<Vec<_>, _>>())
+            .transpose()?;
+        // All the names, they are listed by register number
+        let command = MiCommand::data_list_register_names(None);
+        let response = self.execute(&command).await?;
+        let names: Vec<String> = serde_json::from_value(
+            response
+                .results
+                .get("register-names")
+                .ok_or(AppError::NotFound("register-names not found".to_string()))?
+                .to_owned(),
+        )?;
+
+        let command =
+            MiCommand::data_list_register_values(RegisterFormat::Hex, thread_id, reg_list);
+        let response = self.execute(&command).await?;
+
+        let registers: Vec<Register> = serde_json::from_value(
+            response
+                .results
+                .get("register-values")
+                .ok_or(AppError::NotFound("expect register-values".to_string()))?
+                .to_owned(),
+        )?;
+        Ok(registers
+            .into_iter()
+            .map(|mut r| {
+                r.name = names.get(r.number).cloned();
+                r
+            })
+            .collect::<_>())
+    }
 }
 
 impl GDBManager {
@@ -74,50 +549,63 @@ impl GDBManager {
             opt_args: args.unwrap_or(vec![]),
             opt_program: program,
             opt_tty: tty,
+            transcript: self
+                .config
+                .transcript_dir
+                .as_ref()
+                .map(|dir| dir.join(format!("{}.jsonl", session_id))),
         };
+        let transcript = gdb_builder.transcript.clone();
 
-        let (oob_src, mut oob_sink) = mpsc::channel(100);
+        let (oob_src, oob_sink) = mpsc::channel(100);
         let gdb = gdb_builder.try_spawn(oob_src)?;
-
-        let oob_handle = tokio::spawn(async move {
-            loop {
-                match oob_sink.recv().await {
-                    Some(record) => match record {
-                        OutOfBandRecord::AsyncRecord { results, .. } => {
-                            let transport = TRANSPORT.lock().await;
-                            if let Some(transport) = transport.as_ref() {
-                                if let Err(e) = transport
-                                    .send_notification("create_session", Some(results))
-                                    .await
-                                {
-                                    error!("Failed to send ping to session: {:?}", e);
-                                }
-                            } else {
-                                warn!("Sink Channel closed");
-                                break;
-                            }
-                        }
-                        OutOfBandRecord::StreamRecord { data, .. } => {
-                            debug!("StreamRecord: {:?}", data);
-                        }
-                    },
-                    None => {
-                        debug!("Source Channel closed");
-                        break;
-                    }
-                }
-            }
-        });
+        let (stops, _) = broadcast::channel(100);
+        let (stop_src, stop_sink) = mpsc::unbounded_channel();
+        let (pending_stops, _) = watch::channel(0);
+        let history = Arc::new(Mutex::new(EventHistory::new(self.config.event_history_size)));
+        let breakpoints = Arc::new(Mutex::new(BreakPointSet::default()));
+        let logpoints = Arc::new(Mutex::new(HashMap::new()));
 
         // Create session information
-        let session = GDBSession {
+        let info = Arc::new(Mutex::new(GDBSession {
             id: session_id.clone(),
             status: GDBSessionStatus::Created,
             created_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-        };
+            desynced: false,
+            exit_status: None,
+            stderr_tail: Vec::new(),
+            parse_failures: 0,
+            transcript,
+        }));
+
+        let oob_handle = tokio::spawn(Self::forward_events(
+            oob_sink,
+            info.clone(),
+            history.clone(),
+            stop_src,
+            pending_stops.clone(),
+            breakpoints.clone(),
+            logpoints.clone(),
+        ));
 
         // Store session
-        let handle = GDBSessionHandle { info: session, gdb, oob_handle };
+        let handle = Arc::new(GDBSessionHandle {
+            info,
+            gdb,
+            oob_handle,
+            stops,
+            pending_stops,
+            command_timeout: Duration::from_secs(self.config.command_timeout),
+            history,
+            breakpoints,
+            logpoints,
+            actions: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(SnapshotStore::new(self.config.event_history_size)),
+            panic_breakpoints: Mutex::new(HashSet::new()),
+            cli_catch: Mutex::new(()),
+        });
+        tokio::spawn(handle.clone().handle_stops(stop_sink));
+        tokio::spawn(Self::supervise(handle.clone()));
 
         self.sessions.lock().await.insert(session_id.clone(), handle);
 
@@ -127,20 +615,177 @@ impl GDBManager {
         Ok(session_id)
     }
 
+    /// Record the out of band records of a session, keep its status up to
+    /// date, queue its stops for the stop handler and forward the records
+    /// as notifications to the client
+    async fn forward_events(
+        mut oob_sink: mpsc::Receiver<AppResult<OutOfBandRecord>>,
+        info: Arc<Mutex<GDBSession>>,
+        history: Arc<Mutex<EventHistory>>,
+        stops: mpsc::UnboundedSender<StopEvent>,
+        pending_stops: watch::Sender<usize>,
+        breakpoints: Arc<Mutex<BreakPointSet>>,
+        logpoints: Arc<Mutex<HashMap<String, BreakPointNumber>>>,
+    ) {
+        let mut caught_signal = None;
+        while let Some(record) = oob_sink.recv().await {
+            let mut record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let event = history.lock().await.push_error(&e).clone();
+                    let mut info = info.lock().await;
+                    if let AppError::ParseError { .. } = e {
+                        info.parse_failures += 1;
+                    }
+                    let session_id = info.id.clone();
+                    drop(info);
+
+                    notify("gdb/error", &SessionNotification { session_id, event }).await;
+                    continue;
+                }
+            };
+            decode_signal_catchpoint(&mut record, &mut caught_signal);
+            // Decoded once, the raw results are kept if they don't decode
+            let decoded = match &record {
+                OutOfBandRecord::AsyncRecord { class, results, .. } => {
+                    Some(AsyncEvent::decode(class, results).unwrap_or_else(|e| {
+                        warn!("Failed to decode {} record: {}", class, e);
+                        AsyncEvent::Other(results.clone())
+                    }))
+                }
+                OutOfBandRecord::StreamRecord { .. } => None,
+            };
+            let event = match Self::logpoint_output(&record, &logpoints).await {
+                Some((number, text)) => history.lock().await.push_logpoint(number, text).clone(),
+                None => history.lock().await.push(&record, decoded.clone()).clone(),
+            };
+
+            // Breakpoints changed with CLI commands, GDB doesn't notify the
+            // changes made with MI commands
+            if let Some(AsyncEvent::Breakpoint(event)) = &decoded {
+                let mut breakpoints = breakpoints.lock().await;
+                match (&event.bkpt, event.id) {
+                    (Some(breakpoint), _) => breakpoints.update_breakpoint(breakpoint.clone()),
+                    (None, Some(number)) => breakpoints.remove_breakpoint(number),
+                    (None, None) => {}
+                }
+            }
+
+            let mut info = info.lock().await;
+            if let OutOfBandRecord::AsyncRecord { class, .. } = &record {
+                match (class, &decoded) {
+                    // Late records don't revive a session whose GDB is gone
+                    _ if info.status == GDBSessionStatus::Terminated => {}
+                    (AsyncClass::Running, _) => info.status = GDBSessionStatus::Running,
+                    (AsyncClass::Stopped, Some(AsyncEvent::Stopped(stop))) => {
+                        let exited = stop.reason.as_ref().is_some_and(|reason| reason.is_exit());
+                        info.status = if exited {
+                            GDBSessionStatus::Exited
+                        } else {
+                            GDBSessionStatus::Stopped
+                        };
+                        // Counted before it's queued, so that it can't be
+                        // handled before it's counted
+                        pending_stops.send_modify(|pending| *pending += 1);
+                        if stops.send(stop.as_ref().clone()).is_err() {
+                            pending_stops.send_modify(|pending| *pending -= 1);
+                        }
+                    }
+                    // A stop that doesn't decode has nothing to handle
+                    (AsyncClass::Stopped, _) => info.status = GDBSessionStatus::Stopped,
+                    _ => {}
+                }
+            }
+            let session_id = info.id.clone();
+            drop(info);
+
+            let method = event.method();
+            notify(&method, &SessionNotification { session_id, event }).await;
+        }
+        debug!("Source Channel closed");
+    }
+
+    /// The logpoint and the text it printed, if `record` is the output of a
+    /// logpoint
+    async fn logpoint_output<'a>(
+        record: &'a OutOfBandRecord,
+        logpoints: &Mutex<HashMap<String, BreakPointNumber>>,
+    ) -> Option<(BreakPointNumber, &'a str)> {
+        let OutOfBandRecord::StreamRecord { kind: StreamKind::Console, data } = record else {
+            return None;
+        };
+        let (tag, text) = data.strip_prefix(LOGPOINT_MARKER)?.split_once("] ")?;
+        let number = logpoints.lock().await.get(tag).copied()?;
+        Some((number, text))
+    }
+
+    /// Wait for the GDB process of a session to exit, then mark the session
+    /// as terminated and notify the client
+    async fn supervise(handle: Arc<GDBSessionHandle>) {
+        let exit_status = handle.gdb.wait_exit().await;
+        let stderr_tail = handle.gdb.stderr_tail();
+
+        let mut info = handle.info.lock().await;
+        info.status = GDBSessionStatus::Terminated;
+        info.exit_status = Some(exit_status.clone());
+        info.stderr_tail = stderr_tail.clone();
+        let session_id = info.id.clone();
+        drop(info);
+
+        warn!("GDB of session {} exited: {}", session_id, exit_status);
+        notify(
+            "gdb/terminated",
+            &json!({
+                "session_id": session_id,
+                "exit_status": exit_status,
+                "stderr_tail": stderr_tail,
+            }),
+        )
+        .await;
+    }
+
     /// Get all sessions
     pub async fn get_all_sessions(&self) -> AppResult<Vec<GDBSession>> {
-        let sessions = self.sessions.lock().await;
-        let result = sessions.values().map(|handle| handle.info.clone()).collect();
+        let handles: Vec<_> = self.sessions.lock().await.values().cloned().collect();
+        let mut result = Vec::with_capacity(handles.len());
+        for handle in handles {
+            result.push(handle.info.lock().await.clone());
+        }
         Ok(result)
     }
 
     /// Get specific session
     pub async fn get_session(&self, session_id: &str) -> AppResult<GDBSession> {
-        let sessions = self.sessions.lock().await;
-        let handle = sessions
+        let handle = self.get_handle(session_id).await?;
+        let info = handle.info.lock().await.clone();
+        Ok(info)
+    }
+
+    /// Get the recorded async and stream records of a session, optionally
+    /// filtered by kind and class, `since` is the sequence number of the last
+    /// event already seen
+    pub async fn get_session_events(
+        &self,
+        session_id: &str,
+        kind: Option<String>,
+        class: Option<String>,
+        since: Option<u64>,
+        limit: Option<usize>,
+    ) -> AppResult<Vec<SessionEvent>> {
+        let handle = self.get_handle(session_id).await?;
+        let history = handle.history.lock().await;
+        Ok(history.query(kind.as_deref(), class.as_deref(), since, limit.unwrap_or(100)))
+    }
+
+    /// Look up a session handle, the session table is only locked for the
+    /// lookup so commands of different sessions don't serialize on it
+    async fn get_handle(&self, session_id: &str) -> AppResult<Arc<GDBSessionHandle>> {
+        self.sessions
+            .lock()
+            .await
             .get(session_id)
-            .ok_or_else(|| AppError::NotFound(format!("Session {} does not exist", session_id)))?;
-        Ok(handle.info.clone())
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Session {} does not exist", session_id)))
     }
 
     /// Close session
@@ -154,14 +799,12 @@ impl GDBManager {
             }
         };
 
-        let mut sessions = self.sessions.lock().await;
-        let handle = sessions.remove(session_id);
+        let handle = self.sessions.lock().await.remove(session_id);
 
         if let Some(handle) = handle {
             handle.oob_handle.abort();
-            // Terminate process
-            let mut process = handle.gdb.process.lock().await;
-            let _ = process.kill().await; // Ignore possible errors, process may have already terminated
+            // Terminate process, it may have already terminated
+            handle.gdb.kill().await;
         }
 
         Ok(())
@@ -173,12 +816,10 @@ impl GDBManager {
         session_id: &str,
         command: &MiCommand,
     ) -> AppResult<ResultRecord> {
-        let mut sessions = self.sessions.lock().await;
-        let handle = sessions
-            .get_mut(session_id)
-            .ok_or_else(|| AppError::NotFound(format!("Session {} does not exist", session_id)))?;
+        let handle = self.get_handle(session_id).await?;
 
         let record = handle.gdb.execute(command).await?;
+        handle.info.lock().await.desynced = false;
         let output = record.results.to_string();
 
         debug!("GDB output: {}", output);
@@ -191,44 +832,252 @@ impl GDBManager {
         session_id: &str,
         command: &MiCommand,
     ) -> AppResult<ResultRecord> {
-        let command_timeout = self.config.command_timeout;
-        match tokio::time::timeout(
-            Duration::from_secs(command_timeout),
-            self.send_command(session_id, command),
-        )
-        .await
+        self.get_handle(session_id).await?.execute(command).await
+    }
+
+    /// Start debugging
+    pub async fn start_debugging(
+        &self,
+        session_id: &str,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        self.execute_and_wait(session_id, &MiCommand::exec_run(), wait, timeout).await
+    }
+
+    /// Stop debugging, interrupts the running target and returns the frame it
+    /// stopped at
+    pub async fn stop_debugging(&self, session_id: &str) -> AppResult<StackFrame> {
+        let handle = self.get_handle(session_id).await?;
+        if !handle.gdb.is_running() {
+            return Err(AppError::GDBError("The program is not running".to_string()));
+        }
+        let command_timeout = handle.command_timeout;
+        // Subscribe before interrupting so that the stop can't be missed
+        let mut stops = handle.stops.subscribe();
+
+        let interrupted = match tokio::time::timeout(command_timeout, handle.gdb.interrupt()).await
         {
-            Ok(Ok(result)) => Ok(result),
-            Ok(Err(e)) => Err(e),
+            Ok(result) => result,
             Err(_) => Err(AppError::GDBTimeout),
+        };
+        let stopped = match interrupted {
+            Ok(()) => Self::wait_for_stopped(&handle.gdb, &mut stops, command_timeout).await,
+            Err(e) => Err(e),
+        };
+        let stopped = match stopped {
+            Ok(stopped) => stopped,
+            Err(e) => {
+                // -exec-interrupt does not always interrupt the target
+                warn!("Failed to interrupt with -exec-interrupt: {}, sending SIGINT", e);
+                handle
+                    .gdb
+                    .interrupt_execution()
+                    .await
+                    .map_err(|e| AppError::GDBError(format!("Failed to send SIGINT: {}", e)))?;
+                Self::wait_for_stopped(&handle.gdb, &mut stops, command_timeout).await?
+            }
+        };
+
+        stopped.frame.ok_or(AppError::NotFound("frame not found in the stop record".to_string()))
+    }
+
+    /// Send an execution command and, if asked to, wait until the target
+    /// stops again. If it doesn't stop within the timeout (the command
+    /// timeout by default) it is left running.
+    async fn execute_and_wait(
+        &self,
+        session_id: &str,
+        command: &MiCommand,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let handle = self.get_handle(session_id).await?;
+        // A stop still being handled could resume the target or be taken
+        // for the stop of this command
+        let _ = handle.pending_stops.subscribe().wait_for(|pending| *pending == 0).await;
+        // Subscribe before resuming so that a quick stop can't be missed
+        let mut stops = handle.stops.subscribe();
+
+        let response = handle.execute(command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
         }
+
+        Ok(if wait {
+            let timeout = Duration::from_secs(timeout.unwrap_or(self.config.command_timeout));
+            match Self::wait_for_reasons(&handle.gdb, &mut stops, timeout, None).await {
+                Ok(stopped) => ExecutionOutcome::Stopped(Box::new(stopped)),
+                Err(AppError::GDBTimeout) => ExecutionOutcome::Running(response.results),
+                Err(e) => return Err(e),
+            }
+        } else {
+            ExecutionOutcome::Running(response.results)
+        })
     }
 
-    /// Start debugging
-    pub async fn start_debugging(&self, session_id: &str) -> AppResult<String> {
-        let response = self.send_command_with_timeout(session_id, &MiCommand::exec_run()).await?;
+    /// Send an execution command like [`Self::execute_and_wait`], a stop also
+    /// reports the instruction the target stopped at
+    async fn execute_and_disassemble(
+        &self,
+        session_id: &str,
+        command: &MiCommand,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let mut outcome = self.execute_and_wait(session_id, command, wait, timeout).await?;
+        if let ExecutionOutcome::Stopped(stop) = &mut outcome
+            && let Some(address) = stop.frame.as_ref().and_then(|frame| frame.address)
+        {
+            match self.disassemble_instruction(session_id, address.0 as usize).await {
+                Ok(instruction) => stop.instruction = Some(instruction),
+                Err(e) => warn!("Failed to disassemble at {}: {}", String::from(address), e),
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Send an execution command forwards or, through the recorded history,
+    /// backwards. Stops running backwards also report the instruction.
+    async fn execute_in_direction(
+        &self,
+        session_id: &str,
+        command: MiCommand,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        if reverse {
+            self.execute_and_disassemble(session_id, &command.reverse(), wait, timeout).await
+        } else {
+            self.execute_and_wait(session_id, &command, wait, timeout).await
+        }
+    }
 
-        // Update session status
-        let mut sessions = self.sessions.lock().await;
-        if let Some(handle) = sessions.get_mut(session_id) {
-            handle.info.status = GDBSessionStatus::Running;
+    /// The instruction at `address`
+    async fn disassemble_instruction(
+        &self,
+        session_id: &str,
+        address: usize,
+    ) -> AppResult<Instruction> {
+        let command = MiCommand::data_disassemble_address(
+            address,
+            address + 1,
+            DisassembleMode::DisassemblyOnly,
+        );
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
         }
 
-        Ok(response.results.to_string())
+        let instructions: Vec<Instruction> = serde_json::from_value(
+            response
+                .results
+                .get("asm_insns")
+                .ok_or(AppError::NotFound("asm_insns not found in the result".to_string()))?
+                .to_owned(),
+        )?;
+        instructions
+            .into_iter()
+            .next()
+            .ok_or(AppError::NotFound(format!("no instruction at 0x{:x}", address)))
+    }
+
+    /// Wait until the target of a session stops. If `reasons` are given,
+    /// stops with other reasons are skipped without resuming the target,
+    /// exits are always reported.
+    pub async fn wait_for_stop(
+        &self,
+        session_id: &str,
+        reasons: Option<Vec<String>>,
+        timeout: Option<u64>,
+    ) -> AppResult<StopEvent> {
+        let reasons = reasons.as_deref().map(parse_stop_reasons).transpose()?;
+        let handle = self.get_handle(session_id).await?;
+        let mut stops = handle.stops.subscribe();
+        let timeout = Duration::from_secs(timeout.unwrap_or(self.config.command_timeout));
+
+        Self::wait_for_reasons(&handle.gdb, &mut stops, timeout, reasons.as_deref()).await
     }
 
-    /// Stop debugging
-    pub async fn stop_debugging(&self, session_id: &str) -> AppResult<String> {
-        let response =
-            self.send_command_with_timeout(session_id, &MiCommand::exec_interrupt()).await?;
+    /// Wait for the next stop with one of `reasons` if given, or an exit.
+    /// Other stops are skipped and left to whoever drives the target.
+    async fn wait_for_reasons(
+        gdb: &GDB,
+        stops: &mut broadcast::Receiver<StopEvent>,
+        timeout: Duration,
+        reasons: Option<&[StopReason]>,
+    ) -> AppResult<StopEvent> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let stopped = Self::wait_for_stopped(gdb, stops, remaining).await?;
+            let wanted = reasons.is_none_or(|reasons| {
+                stopped.reason.as_ref().is_some_and(|reason| reasons.contains(reason))
+            });
+            if wanted || stopped.reason.as_ref().is_some_and(StopReason::is_exit) {
+                return Ok(stopped);
+            }
+            debug!("Skipping stop: {:?}", stopped.reason);
+        }
+    }
 
-        // Update session status
-        let mut sessions = self.sessions.lock().await;
-        if let Some(handle) = sessions.get_mut(session_id) {
-            handle.info.status = GDBSessionStatus::Stopped;
+    /// Set the actions run when a breakpoint is hit, empty actions remove
+    /// them
+    pub async fn set_breakpoint_actions(
+        &self,
+        session_id: &str,
+        breakpoint: &str,
+        actions: BreakPointActions,
+    ) -> AppResult<()> {
+        let number = parse_breakpoint_numbers(&[breakpoint])?[0];
+        let handle = self.get_handle(session_id).await?;
+        let mut all = handle.actions.lock().await;
+        if actions == BreakPointActions::default() {
+            all.remove(&number.major);
+        } else {
+            all.insert(number.major, actions);
         }
+        Ok(())
+    }
+
+    /// Get the last `limit` snapshots collected at a breakpoint, oldest first
+    pub async fn get_breakpoint_snapshots(
+        &self,
+        session_id: &str,
+        breakpoint: &str,
+        limit: Option<usize>,
+    ) -> AppResult<Vec<BreakPointSnapshot>> {
+        let number = parse_breakpoint_numbers(&[breakpoint])?[0];
+        let handle = self.get_handle(session_id).await?;
+        let snapshots = handle.snapshots.lock().await;
+        Ok(snapshots.last(number.major, limit.unwrap_or(100)))
+    }
 
-        Ok(response.results.to_string())
+    /// Wait for the next stop once handled
+    async fn wait_for_stopped(
+        gdb: &GDB,
+        stops: &mut broadcast::Receiver<StopEvent>,
+        timeout: Duration,
+    ) -> AppResult<StopEvent> {
+        let wait = async {
+            loop {
+                match stops.recv().await {
+                    Ok(stopped) => return Ok(stopped),
+                    Err(RecvError::Lagged(n)) => warn!("Missed {} stops", n),
+                    Err(RecvError::Closed) => return Err(AppError::GDBQuit),
+                }
+            }
+        };
+        tokio::time::timeout(timeout, async {
+            tokio::select! {
+                stopped = wait => stopped,
+                // The target never stops if GDB is gone
+                _ = gdb.wait_exit() => Err(AppError::GDBQuit),
+            }
+        })
+        .await
+        .map_err(|_| AppError::GDBTimeout)?
     }
 
     /// Get breakpoint list
@@ -241,26 +1090,190 @@ impl GDBManager {
             .get("BreakpointTable")
             .ok_or(AppError::NotFound("BreakpointTable not found".to_string()))?;
         let body = table.get("body").ok_or(AppError::NotFound("body not found".to_string()))?;
-        Ok(serde_json::from_value(body.to_owned())?)
+        let breakpoints: Vec<BreakPoint> = serde_json::from_value(body.to_owned())?;
+
+        let handle = self.get_handle(session_id).await?;
+        handle.breakpoints.lock().await.replace(breakpoints.clone());
+        Ok(breakpoints)
+    }
+
+    /// Update the breakpoints of a session after changing them with a
+    /// command, which GDB doesn't notify
+    async fn refresh_breakpoints(&self, session_id: &str) {
+        if let Err(e) = self.get_breakpoints(session_id).await {
+            warn!("Failed to refresh the breakpoints of session {}: {}", session_id, e);
+        }
     }
 
     /// Set breakpoint
     pub async fn set_breakpoint(
         &self,
         session_id: &str,
-        file: &Path,
-        line: usize,
+        location: BreakPointLocation<'_>,
+        options: &BreakPointOptions,
     ) -> AppResult<BreakPoint> {
-        let command = MiCommand::insert_breakpoint(BreakPointLocation::Line(file, line));
+        let command = MiCommand::insert_breakpoint(location, options);
         let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
 
-        Ok(serde_json::from_value(
+        let breakpoint: BreakPoint = serde_json::from_value(
             response
                 .results
                 .get("bkpt")
                 .ok_or(AppError::NotFound("bkpt not found in the result".to_string()))?
                 .to_owned(),
-        )?)
+        )?;
+        let handle = self.get_handle(session_id).await?;
+        handle.breakpoints.lock().await.update_breakpoint(breakpoint.clone());
+        Ok(breakpoint)
+    }
+
+    /// Set logpoint, a dynamic printf printing `format` with `arguments`
+    /// like printf without stopping the target. Its output is kept in the
+    /// event history of the session.
+    pub async fn set_logpoint(
+        &self,
+        session_id: &str,
+        location: BreakPointLocation<'_>,
+        options: &BreakPointOptions,
+        format: &str,
+        arguments: &[String],
+    ) -> AppResult<BreakPoint> {
+        // The output is told apart from the rest of the console by this tag,
+        // the number is only known once the logpoint is set
+        let tag = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let format = format!("{}{}] {}", LOGPOINT_MARKER, tag, format);
+        let command = MiCommand::insert_dprintf(location, options, &format, arguments);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+
+        let logpoint: BreakPoint = serde_json::from_value(
+            response
+                .results
+                .get("bkpt")
+                .ok_or(AppError::NotFound("bkpt not found in the result".to_string()))?
+                .to_owned(),
+        )?;
+        let handle = self.get_handle(session_id).await?;
+        handle.logpoints.lock().await.insert(tag, logpoint.number);
+        handle.breakpoints.lock().await.update_breakpoint(logpoint.clone());
+        Ok(logpoint)
+    }
+
+    /// Get the lines printed by a logpoint, oldest first
+    pub async fn get_logpoint_output(
+        &self,
+        session_id: &str,
+        logpoint: &str,
+        since: Option<u64>,
+        limit: Option<usize>,
+    ) -> AppResult<Vec<SessionEvent>> {
+        let number = parse_breakpoint_numbers(&[logpoint])?[0];
+        let handle = self.get_handle(session_id).await?;
+        let history = handle.history.lock().await;
+        Ok(history.logpoint_output(number, since, limit.unwrap_or(100)))
+    }
+
+    /// Set watchpoint, the target stops when `expression` is accessed as
+    /// given by `mode`
+    pub async fn set_watchpoint(
+        &self,
+        session_id: &str,
+        expression: &str,
+        mode: WatchMode,
+    ) -> AppResult<Watchpoint> {
+        let command = MiCommand::insert_watchpoint(expression, mode);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+
+        let key = match mode {
+            WatchMode::Write => "wpt",
+            WatchMode::Read => "hw-rwpt",
+            WatchMode::Access => "hw-awpt",
+        };
+        let watchpoint = serde_json::from_value(
+            response
+                .results
+                .get(key)
+                .ok_or(AppError::NotFound(format!("{} not found in the result", key)))?
+                .to_owned(),
+        )?;
+        self.refresh_breakpoints(session_id).await;
+        Ok(watchpoint)
+    }
+
+    /// Set catchpoint, the target stops at `event`. `filter` narrows it down
+    /// as described in [`MiCommand::insert_catchpoint`].
+    pub async fn set_catchpoint(
+        &self,
+        session_id: &str,
+        event: CatchEvent,
+        filter: Option<&str>,
+        temporary: bool,
+    ) -> AppResult<BreakPoint> {
+        let command = MiCommand::insert_catchpoint(event, filter, temporary);
+        let handle = self.get_handle(session_id).await?;
+        if event.is_exception() {
+            let response = self.send_command_with_timeout(session_id, &command).await?;
+            if response.class == ResultClass::Error {
+                return Err(command_error(&response.results));
+            }
+            let bkpt = response
+                .results
+                .get("bkpt")
+                .ok_or(AppError::NotFound("bkpt not found".to_string()))?;
+            let catchpoint: BreakPoint = serde_json::from_value(bkpt.to_owned())?;
+            handle.breakpoints.lock().await.update_breakpoint(catchpoint.clone());
+            return Ok(catchpoint);
+        }
+
+        // The CLI commands don't report the catchpoint, it's the one that
+        // wasn't in the breakpoint list before. One command at a time, so
+        // that each finds its own.
+        let _cli_catch = handle.cli_catch.lock().await;
+        let before: HashSet<usize> = self
+            .get_breakpoints(session_id)
+            .await?
+            .into_iter()
+            .map(|breakpoint| breakpoint.number.major)
+            .collect();
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+        self.get_breakpoints(session_id)
+            .await?
+            .into_iter()
+            .find(|breakpoint| {
+                breakpoint.catch_type.is_some() && !before.contains(&breakpoint.number.major)
+            })
+            .ok_or(AppError::GDBError(format!("catch {} created no catchpoint", event)))
+    }
+
+    /// Stop when the program panics, at the panic handler
+    /// `rust_begin_unwind` or, if the program has none, at `rust_panic`.
+    /// Stops there report the message and location of the panic.
+    pub async fn catch_rust_panic(&self, session_id: &str) -> AppResult<BreakPoint> {
+        let options = BreakPointOptions::default();
+        let location = BreakPointLocation::Function(None, "rust_begin_unwind");
+        let breakpoint = match self.set_breakpoint(session_id, location, &options).await {
+            Err(AppError::GDBError(e)) => {
+                debug!("Catching panics at rust_panic, rust_begin_unwind not found: {}", e);
+                let location = BreakPointLocation::Function(None, "rust_panic");
+                self.set_breakpoint(session_id, location, &options).await?
+            }
+            result => result?,
+        };
+
+        let handle = self.get_handle(session_id).await?;
+        handle.panic_breakpoints.lock().await.insert(breakpoint.number.major);
+        Ok(breakpoint)
     }
 
     /// Delete breakpoint
@@ -269,41 +1282,268 @@ impl GDBManager {
         session_id: &str,
         breakpoints: Vec<String>,
     ) -> AppResult<()> {
-        let command = MiCommand::delete_breakpoints(
-            breakpoints
-                .iter()
-                .map(|num| serde_json::from_str::<BreakPointNumber>(num))
-                .collect::<Result<Vec<_>, _>>()?,
-        );
+        let numbers = parse_breakpoint_numbers(&breakpoints)?;
+        let command = MiCommand::delete_breakpoints(numbers.clone());
         let response = self.send_command_with_timeout(session_id, &command).await?;
         if response.class != ResultClass::Done {
             return Err(AppError::GDBError(response.results.to_string()));
         }
 
+        let handle = self.get_handle(session_id).await?;
+        let mut panic_breakpoints = handle.panic_breakpoints.lock().await;
+        let mut set = handle.breakpoints.lock().await;
+        for number in numbers {
+            if number.minor.is_none() {
+                panic_breakpoints.remove(&number.major);
+            }
+            set.remove_breakpoint(number);
+        }
         Ok(())
     }
 
-    /// Get stack frames
-    pub async fn get_stack_frames(&self, session_id: &str) -> AppResult<Vec<StackFrame>> {
-        let command = MiCommand::stack_list_frames(None, None);
+    /// Enable breakpoints, or single locations of breakpoints as `major.minor`
+    pub async fn enable_breakpoints(
+        &self,
+        session_id: &str,
+        breakpoints: Vec<String>,
+    ) -> AppResult<()> {
+        let command = MiCommand::enable_breakpoints(&parse_breakpoint_numbers(&breakpoints)?);
         let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class != ResultClass::Done {
+            return Err(command_error(&response.results));
+        }
 
-        Ok(serde_json::from_value(
-            response
-                .results
-                .get("stack")
-                .ok_or(AppError::NotFound("stack not found".to_string()))?
-                .to_owned(),
-        )?)
+        self.refresh_breakpoints(session_id).await;
+        Ok(())
+    }
+
+    /// Disable breakpoints, or single locations of breakpoints as `major.minor`
+    pub async fn disable_breakpoints(
+        &self,
+        session_id: &str,
+        breakpoints: Vec<String>,
+    ) -> AppResult<()> {
+        let command = MiCommand::disable_breakpoints(&parse_breakpoint_numbers(&breakpoints)?);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class != ResultClass::Done {
+            return Err(command_error(&response.results));
+        }
+
+        self.refresh_breakpoints(session_id).await;
+        Ok(())
+    }
+
+    /// Set the condition of a breakpoint, or remove it without a condition
+    pub async fn set_breakpoint_condition(
+        &self,
+        session_id: &str,
+        breakpoint: &str,
+        condition: Option<&str>,
+    ) -> AppResult<()> {
+        let number = parse_breakpoint_numbers(&[breakpoint])?[0];
+        let command = MiCommand::breakpoint_condition(number, condition);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class != ResultClass::Done {
+            return Err(command_error(&response.results));
+        }
+
+        self.refresh_breakpoints(session_id).await;
+        Ok(())
+    }
+
+    /// Ignore the next `count` hits of a breakpoint, 0 stops at the next hit
+    pub async fn set_breakpoint_ignore_count(
+        &self,
+        session_id: &str,
+        breakpoint: &str,
+        count: usize,
+    ) -> AppResult<()> {
+        let number = parse_breakpoint_numbers(&[breakpoint])?[0];
+        let command = MiCommand::breakpoint_after(number, count);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class != ResultClass::Done {
+            return Err(command_error(&response.results));
+        }
+
+        self.refresh_breakpoints(session_id).await;
+        Ok(())
+    }
+
+    /// Write the breakpoints of a session to a JSON file, to set them again
+    /// in another session
+    pub async fn export_breakpoints(
+        &self,
+        session_id: &str,
+        path: &Path,
+    ) -> AppResult<Vec<BreakPointSpec>> {
+        let handle = self.get_handle(session_id).await?;
+        let specs = handle.breakpoints.lock().await.export();
+        tokio::fs::write(path, serde_json::to_string_pretty(&specs)?).await?;
+        Ok(specs)
     }
 
-    /// Get local variables
+    /// Set the breakpoints exported to a JSON file, e.g. after rebuilding the
+    /// program. A breakpoint that can't be set doesn't stop the others.
+    pub async fn import_breakpoints(
+        &self,
+        session_id: &str,
+        path: &Path,
+    ) -> AppResult<RestoredBreakPoints> {
+        let specs: Vec<BreakPointSpec> =
+            serde_json::from_str(&tokio::fs::read_to_string(path).await?)?;
+        let mut restored = RestoredBreakPoints::default();
+        for spec in specs {
+            if let Err(e) = self.import_breakpoint(session_id, &spec, &mut restored).await {
+                restored.failed.push(format!("{}: {}", spec.location, e));
+            }
+        }
+        Ok(restored)
+    }
+
+    async fn import_breakpoint(
+        &self,
+        session_id: &str,
+        spec: &BreakPointSpec,
+        restored: &mut RestoredBreakPoints,
+    ) -> AppResult<()> {
+        let number = match spec.watch {
+            Some(mode) => {
+                let watchpoint = self.set_watchpoint(session_id, &spec.location, mode).await?;
+                let number = watchpoint.number;
+                restored.watchpoints.push(watchpoint);
+                // -break-watch has no options for these
+                let number_str = number.to_string();
+                if let Some(condition) = &spec.condition {
+                    self.set_breakpoint_condition(session_id, &number_str, Some(condition)).await?;
+                }
+                if let Some(count) = spec.ignore_count {
+                    self.set_breakpoint_ignore_count(session_id, &number_str, count).await?;
+                }
+                number
+            }
+            None => {
+                let options = BreakPointOptions {
+                    condition: spec.condition.clone(),
+                    ignore_count: spec.ignore_count,
+                    temporary: spec.temporary,
+                    hardware: spec.hardware,
+                    pending: spec.pending,
+                    thread: spec.thread.map(|thread| thread as usize),
+                };
+                let location = BreakPointLocation::Linespec(&spec.location);
+                let breakpoint = self.set_breakpoint(session_id, location, &options).await?;
+                let number = breakpoint.number;
+                restored.breakpoints.push(breakpoint);
+                number
+            }
+        };
+        if !spec.enabled {
+            self.disable_breakpoints(session_id, vec![number.to_string()]).await?;
+        }
+        Ok(())
+    }
+
+    /// List the threads of the inferior and the selected one
+    pub async fn list_threads(&self, session_id: &str) -> AppResult<ThreadList> {
+        let command = MiCommand::thread_info(None);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+        Ok(serde_json::from_value(response.results)?)
+    }
+
+    /// Select the thread later commands apply to by default
+    pub async fn select_thread(&self, session_id: &str, thread_id: usize) -> AppResult<Thread> {
+        let command = MiCommand::thread_select(thread_id);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+
+        let command = MiCommand::thread_info(Some(thread_id as u64));
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+        let list: ThreadList = serde_json::from_value(response.results)?;
+        list.threads
+            .into_iter()
+            .next()
+            .ok_or(AppError::NotFound(format!("thread {} not found", thread_id)))
+    }
+
+    /// Get stack frames of the given thread, by default the selected one
+    pub async fn get_stack_frames(
+        &self,
+        session_id: &str,
+        thread_id: Option<usize>,
+    ) -> AppResult<Vec<StackFrame>> {
+        self.get_frames(session_id, thread_id, None).await
+    }
+
+    /// Get the innermost `depth` frames of the given thread, all by default
+    async fn get_frames(
+        &self,
+        session_id: &str,
+        thread_id: Option<usize>,
+        depth: Option<usize>,
+    ) -> AppResult<Vec<StackFrame>> {
+        self.get_handle(session_id).await?.get_frames(thread_id, depth).await
+    }
+
+    /// Backtraces of all threads, at most `depth` frames each, threads with
+    /// identical stacks are grouped together
+    pub async fn get_all_backtraces(
+        &self,
+        session_id: &str,
+        depth: Option<usize>,
+    ) -> AppResult<AllBacktraces> {
+        let list = self.list_threads(session_id).await?;
+        let mut stacks = Vec::new();
+        let mut running = Vec::new();
+        let mut errors = Vec::new();
+        for thread in &list.threads {
+            if thread.state == ThreadState::Running {
+                running.push(thread.id);
+                continue;
+            }
+            match self.get_frames(session_id, Some(thread.id as usize), depth).await {
+                Ok(frames) => stacks.push((thread.id, frames)),
+                Err(e) => errors.push(format!("thread {}: {}", thread.id, e)),
+            }
+        }
+        Ok(AllBacktraces {
+            threads: list.threads.len(),
+            groups: BacktraceGroup::group(stacks),
+            running: (!running.is_empty()).then_some(running),
+            errors: (!errors.is_empty()).then_some(errors),
+        })
+    }
+
+    /// Evaluate an expression in the given thread and frame, by default the
+    /// selected ones, and return its value formatted as `format` and its type
+    pub async fn evaluate_expression(
+        &self,
+        session_id: &str,
+        expression: &str,
+        thread_id: Option<usize>,
+        frame_id: Option<usize>,
+        format: Option<ValueFormat>,
+    ) -> AppResult<ExpressionValue> {
+        let handle = self.get_handle(session_id).await?;
+        handle.evaluate_expression(expression, thread_id, frame_id, format).await
+    }
+
+    /// Get local variables of a frame of the given thread, by default the
+    /// selected ones
     pub async fn get_local_variables(
         &self,
         session_id: &str,
+        thread_id: Option<usize>,
         frame_id: Option<usize>,
     ) -> AppResult<Vec<Variable>> {
-        let command = MiCommand::stack_list_variables(None, frame_id, None);
+        let command = MiCommand::stack_list_variables(thread_id, frame_id, None);
         let response = self.send_command_with_timeout(session_id, &command).await?;
 
         Ok(serde_json::from_value(
@@ -315,42 +1555,14 @@ impl GDBManager {
         )?)
     }
 
-    /// Get registers
+    /// Get registers of the given thread, by default the selected one
     pub async fn get_registers(
         &self,
         session_id: &str,
+        thread_id: Option<usize>,
         reg_list: Option<Vec<String>>,
     ) -> AppResult<Vec<Register>> {
-        let reg_list = reg_list
-            .map(|s| s.iter().map(|num| num.parse::<usize>()).collect::<Result<Vec<_>, _>>())
-            .transpose()?;
-        let command = MiCommand::data_list_register_names(reg_list.clone());
-        let response = self.send_command_with_timeout(session_id, &command).await?;
-        let names: Vec<String> = serde_json::from_value(
-            response
-                .results
-                .get("register-names")
-                .ok_or(AppError::NotFound("register-names not found".to_string()))?
-                .to_owned(),
-        )?;
-
-        let command = MiCommand::data_list_register_values(RegisterFormat::Hex, reg_list);
-        let response = self.send_command_with_timeout(session_id, &command).await?;
-
-        let registers: Vec<Register> = serde_json::from_value(
-            response
-                .results
-                .get("register-values")
-                .ok_or(AppError::NotFound("expect register-values".to_string()))?
-                .to_owned(),
-        )?;
-        Ok(registers
-            .into_iter()
-            .map(|mut r| {
-                r.name = names.get(r.number).cloned();
-                r
-            })
-            .collect::<_>())
+        self.get_handle(session_id).await?.get_registers(thread_id, reg_list).await
     }
 
     /// Get register names
@@ -394,31 +1606,1410 @@ impl GDBManager {
         )?)
     }
 
-    /// Continue execution
-    pub async fn continue_execution(&self, session_id: &str) -> AppResult<String> {
+    /// Continue execution, backwards if `reverse`
+    pub async fn continue_execution(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_continue();
+        self.execute_in_direction(session_id, command, reverse, wait, timeout).await
+    }
+
+    /// Step execution, backwards if `reverse`
+    pub async fn step_execution(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_step();
+        self.execute_in_direction(session_id, command, reverse, wait, timeout).await
+    }
+
+    /// Next execution, backwards if `reverse`
+    pub async fn next_execution(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_next();
+        self.execute_in_direction(session_id, command, reverse, wait, timeout).await
+    }
+
+    /// Run until the selected frame returns, the stop reports the value it
+    /// returned. Backwards if `reverse`, to the call of the function.
+    pub async fn finish_execution(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_finish();
+        self.execute_in_direction(session_id, command, reverse, wait, timeout).await
+    }
+
+    /// Step one instruction, backwards if `reverse`. The stop reports the
+    /// instruction stepped to.
+    pub async fn step_instruction(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_step_instruction();
+        let command = if reverse { command.reverse() } else { command };
+        self.execute_and_disassemble(session_id, &command, wait, timeout).await
+    }
+
+    /// Step one instruction over calls, backwards if `reverse`. The stop
+    /// reports the instruction stepped to.
+    pub async fn next_instruction(
+        &self,
+        session_id: &str,
+        reverse: bool,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        let command = MiCommand::exec_next_instruction();
+        let command = if reverse { command.reverse() } else { command };
+        self.execute_and_disassemble(session_id, &command, wait, timeout).await
+    }
+
+    /// Step, or step over calls if `over`, until the repeat count is reached
+    /// or the condition holds, waiting up to `timeout` for every step
+    pub async fn repeat_step(
+        &self,
+        session_id: &str,
+        over: bool,
+        reverse: bool,
+        repeat: &StepRepeat,
+        timeout: Option<u64>,
+    ) -> AppResult<SteppingOutcome> {
+        let command = if over { MiCommand::exec_next() } else { MiCommand::exec_step() };
+        let max_steps = repeat.max_steps.unwrap_or(self.config.max_steps);
+        let limit = repeat.count.unwrap_or(max_steps).clamp(1, max_steps.max(1));
+        let start = match repeat.until {
+            Some(StepCondition::LineChanged | StepCondition::FunctionChanged) => {
+                let command = MiCommand::stack_info_frame(None);
+                let response = self.send_command_with_timeout(session_id, &command).await?;
+                if response.class == ResultClass::Error {
+                    return Err(command_error(&response.results));
+                }
+                let frame = response.results.get("frame").cloned().unwrap_or_default();
+                Some(serde_json::from_value::<StackFrame>(frame)?)
+            }
+            _ => None,
+        };
+
+        let mut steps = 0;
+        loop {
+            let last = self
+                .execute_in_direction(session_id, command.clone(), reverse, true, timeout)
+                .await?;
+            steps += 1;
+            let end = match &last {
+                ExecutionOutcome::Running(_) => Some(StepEnd::Running),
+                ExecutionOutcome::Stopped(stop)
+                    if stop.reason != Some(StopReason::EndSteppingRange) =>
+                {
+                    Some(StepEnd::Stopped)
+                }
+                ExecutionOutcome::Stopped(stop) => match &repeat.until {
+                    Some(until)
+                        if self.step_condition(session_id, until, start.as_ref(), stop).await? =>
+                    {
+                        Some(StepEnd::Condition)
+                    }
+                    _ if steps < limit => None,
+                    _ if repeat.count.is_some_and(|count| count <= max_steps) => {
+                        Some(StepEnd::Count)
+                    }
+                    _ => Some(StepEnd::MaxSteps),
+                },
+            };
+            if let Some(end) = end {
+                return Ok(SteppingOutcome { steps, end, last });
+            }
+        }
+    }
+
+    /// Whether the condition ending a repeated step holds at a stop, `start`
+    /// is the frame before the first step
+    async fn step_condition(
+        &self,
+        session_id: &str,
+        condition: &StepCondition,
+        start: Option<&StackFrame>,
+        stop: &StopEvent,
+    ) -> AppResult<bool> {
+        Ok(match (condition, start, &stop.frame) {
+            (StepCondition::Expression(expression), _, _) => {
+                let command = MiCommand::data_evaluate_expression(expression, None, None);
+                let response = self.send_command_with_timeout(session_id, &command).await?;
+                if response.class == ResultClass::Error {
+                    return Err(command_error(&response.results));
+                }
+                let value = response.results.get("value").and_then(|value| value.as_str());
+                is_true(value.unwrap_or_default())
+            }
+            (StepCondition::LineChanged, Some(start), Some(frame)) => {
+                (&start.fullname, start.line) != (&frame.fullname, frame.line)
+            }
+            (StepCondition::FunctionChanged, Some(start), Some(frame)) => {
+                start.function != frame.function
+            }
+            // Without a frame to compare, e.g. after stepping out of main
+            _ => true,
+        })
+    }
+
+    /// Start recording the execution of the target, which has to be running,
+    /// so that it can be run backwards
+    pub async fn start_recording(&self, session_id: &str) -> AppResult<()> {
         let response =
-            self.send_command_with_timeout(session_id, &MiCommand::exec_continue()).await?;
+            self.send_command_with_timeout(session_id, &MiCommand::record_start()).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+        Ok(())
+    }
+
+    /// Stop recording the execution, the recorded history is dropped
+    pub async fn stop_recording(&self, session_id: &str) -> AppResult<()> {
+        let response =
+            self.send_command_with_timeout(session_id, &MiCommand::record_stop()).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+        Ok(())
+    }
+
+    /// Run until a line past the current one, e.g. to leave a loop, or until
+    /// `location` if given. Either way it stops if the selected frame returns.
+    pub async fn until_execution(
+        &self,
+        session_id: &str,
+        location: Option<BreakPointLocation<'_>>,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        self.execute_and_wait(session_id, &MiCommand::exec_until(location), wait, timeout).await
+    }
+
+    /// Run until `location`, stops earlier if the selected frame returns
+    pub async fn advance_execution(
+        &self,
+        session_id: &str,
+        location: BreakPointLocation<'_>,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        self.execute_and_wait(session_id, &MiCommand::exec_advance(location), wait, timeout).await
+    }
+
+    /// Resume execution at `location`
+    pub async fn jump_execution(
+        &self,
+        session_id: &str,
+        location: BreakPointLocation<'_>,
+        wait: bool,
+        timeout: Option<u64>,
+    ) -> AppResult<ExecutionOutcome> {
+        self.execute_and_wait(session_id, &MiCommand::exec_jump(location), wait, timeout).await
+    }
+
+    /// Pop the selected frame without running the rest of its function,
+    /// returning `value` if given. The target doesn't resume, the frame
+    /// returned to is reported instead.
+    pub async fn return_from_function(
+        &self,
+        session_id: &str,
+        value: Option<&str>,
+    ) -> AppResult<StackFrame> {
+        let command = MiCommand::exec_return(value);
+        let response = self.send_command_with_timeout(session_id, &command).await?;
+        if response.class == ResultClass::Error {
+            return Err(command_error(&response.results));
+        }
+
+        Ok(serde_json::from_value(
+            response
+                .results
+                .get("frame")
+                .ok_or(AppError::NotFound("frame not found in the result".to_string()))?
+                .to_owned(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mi::fake::FAKE_GDB_PREFIX;
+    use crate::mi::transcript;
+    use crate::models::Address;
+
+    /// Create a session running the GDB at `gdb_path`
+    async fn session_with(manager: &GDBManager, gdb_path: String) -> String {
+        manager
+            .create_session(
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(PathBuf::from(gdb_path)),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Create a session of `manager` on the fake GDB playing `script`
+    async fn fake_session_with(manager: &GDBManager, script: &str) -> String {
+        let path = std::env::temp_dir().join(format!("fake-gdb-{}.script", Uuid::new_v4()));
+        std::fs::write(&path, script).unwrap();
+        let session_id =
+            session_with(manager, format!("{}{}", FAKE_GDB_PREFIX, path.display())).await;
+        std::fs::remove_file(&path).unwrap();
+        session_id
+    }
+
+    /// Create a session on the fake GDB playing `script`
+    async fn fake_session(script: &str) -> (GDBManager, String) {
+        let manager = GDBManager::default();
+        let session_id = fake_session_with(&manager, script).await;
+        (manager, session_id)
+    }
+
+    /// Wait until the session matches `done`, records are handled in the
+    /// background
+    async fn wait_session(
+        manager: &GDBManager,
+        session_id: &str,
+        done: impl Fn(&GDBSession) -> bool,
+    ) -> GDBSession {
+        for _ in 0..100 {
+            let session = manager.get_session(session_id).await.unwrap();
+            if done(&session) {
+                return session;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("session {} never got there", session_id);
+    }
+
+    #[tokio::test]
+    async fn test_fake_breakpoint_and_run() {
+        let (manager, session_id) = fake_session(
+            r#"
+-> =thread-group-added,id="i1"
+-> *stopped,reason="unterminated
+<- -break-insert "main.c:5"
+-> ^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",thread-groups=["i1"],times="0"}
+<- -exec-run
+-> =thread-group-started,id="i1",pid="42"
+-> ^running
+-> *running,thread-id="all"
+!sleep 20
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x0000000000001139",func="main",args=[],file="main.c",fullname="/src/main.c",line="5",arch="i386:x86-64"},thread-id="1",stopped-threads="all",core="0"
+<- -stack-list-frames*
+-> ^done,stack=[frame={level="0",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",arch="i386:x86-64"}]
+"#,
+        )
+        .await;
+
+        let breakpoint = manager
+            .set_breakpoint(
+                &session_id,
+                BreakPointLocation::Line(Path::new("main.c"), 5),
+                &BreakPointOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(breakpoint.number, BreakPointNumber { major: 1, minor: None });
+
+        match manager.start_debugging(&session_id, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::BreakpointHit));
+                assert_eq!(stop.frame.unwrap().line, Some(5));
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+        let session = manager.get_session(&session_id).await.unwrap();
+        assert_eq!(session.status, GDBSessionStatus::Stopped);
+        assert_eq!(session.parse_failures, 1);
+
+        let frames = manager.get_stack_frames(&session_id, None).await.unwrap();
+        assert_eq!(frames[0].function, "main");
+
+        let stops = manager
+            .get_session_events(&session_id, None, Some("stopped".to_string()), None, None)
+            .await
+            .unwrap();
+        assert_eq!(stops.len(), 1);
+
+        manager.close_session(&session_id).await.unwrap();
+        assert!(matches!(manager.get_session(&session_id).await, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fake_gdb_crash() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-run
+!stderr Fatal signal: Segmentation fault
+!exit 139
+"#,
+        )
+        .await;
+
+        assert!(matches!(
+            manager.start_debugging(&session_id, true, Some(5)).await,
+            Err(AppError::GDBQuit)
+        ));
+        let session = wait_session(&manager, &session_id, |session| {
+            session.status == GDBSessionStatus::Terminated
+        })
+        .await;
+        assert_eq!(session.exit_status.as_deref(), Some("exit status: 139"));
+        assert_eq!(session.stderr_tail, vec!["Fatal signal: Segmentation fault".to_string()]);
+        assert!(matches!(
+            manager.send_command(&session_id, &MiCommand::exec_continue()).await,
+            Err(AppError::GDBQuit)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let script = r#"
+<- -break-insert "main.c:5"
+-> ^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",thread-groups=["i1"],times="0"}
+<- -exec-run
+-> ^running
+-> *running,thread-id="all"
+!sleep 20
+-> *stopped,reason="exited-normally"
+"#;
+        let dir = std::env::temp_dir().join(format!("transcripts-{}", Uuid::new_v4()));
+        let config = Config { transcript_dir: Some(dir.clone()), ..Config::default() };
+        let recording = GDBManager { config, sessions: Default::default() };
+        let session_id = fake_session_with(&recording, script).await;
+        let transcript = recording.get_session(&session_id).await.unwrap().transcript.unwrap();
+        assert_eq!(transcript, dir.join(format!("{}.jsonl", session_id)));
+
+        recording
+            .set_breakpoint(
+                &session_id,
+                BreakPointLocation::Line(Path::new("main.c"), 5),
+                &BreakPointOptions::default(),
+            )
+            .await
+            .unwrap();
+        recording.start_debugging(&session_id, true, Some(5)).await.unwrap();
+        recording.close_session(&session_id).await.unwrap();
+
+        let entries = transcript::load(&transcript).unwrap();
+        assert!(entries.iter().any(|entry| entry.direction == transcript::Direction::Command
+            && entry.line.ends_with("-break-insert \"main.c:5\"")));
+
+        let replaying = GDBManager::default();
+        let session_id = session_with(
+            &replaying,
+            format!("{}{}", transcript::REPLAY_PREFIX, transcript.display()),
+        )
+        .await;
+        let breakpoint = replaying
+            .set_breakpoint(
+                &session_id,
+                BreakPointLocation::Line(Path::new("main.c"), 5),
+                &BreakPointOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(breakpoint.src_pos.unwrap().line, 5);
+        match replaying.start_debugging(&session_id, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::ExitedNormally))
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+        let session = replaying.get_session(&session_id).await.unwrap();
+        assert_eq!(session.status, GDBSessionStatus::Exited);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_expression() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -var-create --thread 1 --frame 2 "-" "*" "name == \"a\\nb\""
+-> ^done,name="var1",numchild="0",value="true",type="bool",thread-id="1",has_more="0"
+<- -var-evaluate-expression -f hexadecimal var1
+-> ^done,value="0x1"
+<- -var-delete -c var1
+-> ^done,ndeleted="1"
+<- -var-create "-" "*" "nope"
+-> ^error,msg="No symbol \"nope\" in current context."
+"#,
+        )
+        .await;
+
+        let value = manager
+            .evaluate_expression(
+                &session_id,
+                r#"name == "a\nb""#,
+                Some(1),
+                Some(2),
+                Some(ValueFormat::Hexadecimal),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value.value.as_deref(), Some("0x1"));
+        assert_eq!(value.r#type.as_deref(), Some("bool"));
+
+        assert!(matches!(
+            manager.evaluate_expression(&session_id, "nope", None, None, None).await,
+            Err(AppError::GDBError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_breakpoint_options() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -break-insert -t -h -c "argc > 1" -i 2 -p 1 "main"
+-> ^done,bkpt={number="1",type="hw breakpoint",disp="del",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",thread-groups=["i1"],cond="argc > 1",thread="1",times="0",ignore="2",original-location="main"}
+<- -break-insert -f "lib.c:parse"
+-> ^done,bkpt={number="2",type="breakpoint",disp="keep",enabled="y",addr="<PENDING>",pending="lib.c:parse",times="0",original-location="lib.c:parse"}
+<- -break-insert *0x1139
+-> ^done,bkpt={number="3",type="breakpoint",disp="keep",enabled="y",addr="<MULTIPLE>",times="0",original-location="*0x1139",locations=[{number="3.1",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",thread-groups=["i1"]}]}
+<- -break-insert "nope"
+-> ^error,msg="Function \"nope\" not defined."
+<- -break-insert "main.c:5\n-gdb-exit"
+-> ^error,msg="malformed linespec error: unexpected string, \"\\n-gdb-exit\""
+"#,
+        )
+        .await;
+
+        let options = BreakPointOptions {
+            condition: Some("argc > 1".to_string()),
+            ignore_count: Some(2),
+            temporary: true,
+            hardware: true,
+            pending: false,
+            thread: Some(1),
+        };
+        let breakpoint = manager
+            .set_breakpoint(&session_id, BreakPointLocation::Function(None, "main"), &options)
+            .await
+            .unwrap();
+        assert_eq!(breakpoint.condition.as_deref(), Some("argc > 1"));
+        assert_eq!(breakpoint.ignore_count, Some(2));
+        assert_eq!(breakpoint.thread, Some(1));
+        assert_eq!(breakpoint.display, "del");
+
+        let options = BreakPointOptions { pending: true, ..Default::default() };
+        let location = BreakPointLocation::Function(Some(Path::new("lib.c")), "parse");
+        let breakpoint = manager.set_breakpoint(&session_id, location, &options).await.unwrap();
+        assert_eq!(breakpoint.pending.as_deref(), Some("lib.c:parse"));
+        assert_eq!(breakpoint.address, None);
+
+        let breakpoint = manager
+            .set_breakpoint(&session_id, BreakPointLocation::Address(0x1139), &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(breakpoint.times, Some(0));
+        assert_eq!(breakpoint.locations.unwrap()[0].function.as_deref(), Some("main"));
+
+        assert!(matches!(
+            manager
+                .set_breakpoint(
+                    &session_id,
+                    BreakPointLocation::Function(None, "nope"),
+                    &Default::default()
+                )
+                .await,
+            Err(AppError::GDBError(_))
+        ));
+        // Quoted, a line break can't start another command
+        let location = BreakPointLocation::Linespec("main.c:5\n-gdb-exit");
+        assert!(matches!(
+            manager.set_breakpoint(&session_id, location, &Default::default()).await,
+            Err(AppError::GDBError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_modify_breakpoints() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -break-disable 1 2.1
+-> ^done
+<- -break-enable 2.1
+-> ^done
+<- -break-condition 1 "i == 3"
+-> ^done
+<- -break-condition 1
+-> ^done
+<- -break-after 1 4
+-> ^done
+<- -break-enable 7
+-> ^error,msg="Bad breakpoint number '7'"
+<- -break-enable 3
+-> ^error,msg="Hardware breakpoints used exceeds limit."
+<- -break-delete 1 2.1
+-> ^done
+"#,
+        )
+        .await;
+
+        manager.disable_breakpoints(&session_id, vec!["1".into(), "2.1".into()]).await.unwrap();
+        manager.enable_breakpoints(&session_id, vec!["2.1".into()]).await.unwrap();
+        manager.set_breakpoint_condition(&session_id, "1", Some("i == 3")).await.unwrap();
+        manager.set_breakpoint_condition(&session_id, "1", None).await.unwrap();
+        manager.set_breakpoint_ignore_count(&session_id, "1", 4).await.unwrap();
+        assert!(matches!(
+            manager.enable_breakpoints(&session_id, vec!["7".into()]).await,
+            Err(AppError::GDBError(_))
+        ));
+        assert!(matches!(
+            manager.enable_breakpoints(&session_id, vec!["3".into()]).await,
+            Err(AppError::HardwareSlotsExhausted(_))
+        ));
+        assert!(matches!(
+            manager.enable_breakpoints(&session_id, vec!["1.x".into()]).await,
+            Err(AppError::InvalidArgument(_))
+        ));
+        manager.delete_breakpoint(&session_id, vec!["1".into(), "2.1".into()]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watchpoints() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -break-watch "counter"
+-> ^done,wpt={number="2",exp="counter"}
+<- -break-watch -r -- "flags"
+-> ^done,hw-rwpt={number="3",exp="flags"}
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="watchpoint-trigger",wpt={number="2",exp="counter"},value={old="1",new="2"},frame={addr="0x0000000000001150",func="main",args=[],file="main.c",fullname="/src/main.c",line="7"},thread-id="1",stopped-threads="all",core="0"
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="2",nr_cols="6",hdr=[],body=[bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",times="1"},bkpt={number="2",type="hw watchpoint",disp="keep",enabled="y",addr="",what="counter",times="1"}]}
+<- -break-watch -a -- "buffer"
+-> ^done,hw-awpt={number="4",exp="buffer"}
+<- -exec-continue
+-> ^error,msg="Could not insert hardware watchpoint 4.\nCould not insert hardware breakpoints:\nYou may have requested too many hardware breakpoints/watchpoints.\n"
+"#,
+        )
+        .await;
+
+        let watchpoint =
+            manager.set_watchpoint(&session_id, "counter", WatchMode::Write).await.unwrap();
+        assert_eq!(watchpoint.number, BreakPointNumber { major: 2, minor: None });
+        let watchpoint =
+            manager.set_watchpoint(&session_id, "flags", WatchMode::Read).await.unwrap();
+        assert_eq!(watchpoint.expression, "flags");
+
+        match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::WatchpointTrigger));
+                assert_eq!(stop.watchpoint.unwrap().expression, "counter");
+                let value = stop.value.unwrap();
+                assert_eq!(value.old.as_deref(), Some("1"));
+                assert_eq!(value.new.as_deref(), Some("2"));
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        let breakpoints = manager.get_breakpoints(&session_id).await.unwrap();
+        assert_eq!(breakpoints[0].watch_mode(), None);
+        assert_eq!(breakpoints[1].watch_mode(), Some(WatchMode::Write));
+        assert_eq!(breakpoints[1].what.as_deref(), Some("counter"));
+        assert_eq!(breakpoints[1].address, None);
+
+        manager.set_watchpoint(&session_id, "buffer", WatchMode::Access).await.unwrap();
+        assert!(matches!(
+            manager.continue_execution(&session_id, false, true, Some(5)).await,
+            Err(AppError::HardwareSlotsExhausted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_breakpoints() {
+        let manager = GDBManager::default();
+        let first = fake_session_with(
+            &manager,
+            r#"
+-> =breakpoint-created,bkpt={number="2",type="breakpoint",disp="keep",enabled="n",addr="0x0000000000001160",func="helper",file="main.c",fullname="/src/main.c",line="12",times="0",original-location="helper"}
+-> =breakpoint-created,bkpt={number="3",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001170",times="0",original-location="gone"}
+-> =breakpoint-deleted,id="3"
+<- -break-insert -c "i > 2" "main.c:5"
+-> ^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",cond="i > 2",times="0",original-location="main.c:5"}
+"#,
+        )
+        .await;
+        let options =
+            BreakPointOptions { condition: Some("i > 2".to_string()), ..Default::default() };
+        manager
+            .set_breakpoint(&first, BreakPointLocation::Line(Path::new("main.c"), 5), &options)
+            .await
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("breakpoints-{}.json", Uuid::new_v4()));
+        let mut specs = vec![];
+        // The notifications are handled in the background
+        for _ in 0..100 {
+            specs = manager.export_breakpoints(&first, &path).await.unwrap();
+            if specs.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].location, "main.c:5");
+        assert_eq!(specs[0].condition.as_deref(), Some("i > 2"));
+        assert!(specs[0].enabled);
+        assert_eq!(specs[1].location, "helper");
+        assert!(!specs[1].enabled);
+
+        let second = fake_session_with(
+            &manager,
+            r#"
+<- -break-insert -c "i > 2" "main.c:5"
+-> ^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/src/main.c",line="5",cond="i > 2",times="0",original-location="main.c:5"}
+<- -break-insert "helper"
+-> ^error,msg="Function \"helper\" not defined."
+"#,
+        )
+        .await;
+        let restored = manager.import_breakpoints(&second, &path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.breakpoints.len(), 1);
+        assert_eq!(restored.breakpoints[0].condition.as_deref(), Some("i > 2"));
+        assert_eq!(restored.failed.len(), 1);
+        assert!(restored.failed[0].starts_with("helper: "));
+    }
+
+    #[tokio::test]
+    async fn test_logpoints() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -dprintf-insert -c "i > 1" "main.c:7" "[logpoint:*
+-> ^done,bkpt={number="2",type="dprintf",disp="keep",enabled="y",addr="0x0000000000001150",func="main",file="main.c",fullname="/src/main.c",line="7",cond="i > 1",times="0",script={"printf \"[logpoint:feedbeef] i=%d\\n\",i"},original-location="main.c:7"}
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> ~"[logpoint:feedbeef] i=2\n"
+-> ~"unrelated\n"
+-> ~"[logpoint:feedbeef] i=3\n"
+-> *stopped,reason="exited-normally"
+"#,
+        )
+        .await;
+
+        let options =
+            BreakPointOptions { condition: Some("i > 1".to_string()), ..Default::default() };
+        let location = BreakPointLocation::Line(Path::new("main.c"), 7);
+        let logpoint = manager
+            .set_logpoint(&session_id, location, &options, "i=%d\\n", &["i".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(logpoint.r#type, "dprintf");
+        assert_eq!(logpoint.script.unwrap().len(), 1);
+
+        // The tag is random, use the one of the script
+        let handle = manager.get_handle(&session_id).await.unwrap();
+        let mut logpoints = handle.logpoints.lock().await;
+        let tag = logpoints.keys().next().unwrap().clone();
+        let number = logpoints.remove(&tag).unwrap();
+        logpoints.insert("feedbeef".to_string(), number);
+        drop(logpoints);
+
+        manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap();
+        let output = manager.get_logpoint_output(&session_id, "2", None, None).await.unwrap();
+        let lines: Vec<_> = output.iter().map(|e| e.data.as_deref().unwrap()).collect();
+        assert_eq!(lines, vec!["i=2\n", "i=3\n"]);
+        assert_eq!(output[0].method(), "gdb/logpoint");
+
+        let since = Some(output[0].seq);
+        let output = manager.get_logpoint_output(&session_id, "2", since, None).await.unwrap();
+        assert_eq!(output.len(), 1);
+        assert!(
+            manager.get_logpoint_output(&session_id, "3", None, None).await.unwrap().is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_breakpoint_actions() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x0000000000001139",func="tick",args=[],file="main.c",fullname="/src/main.c",line="5"},thread-id="1",stopped-threads="all"
+<- -var-create --thread 1 "-" "*" "counter"
+-> ^done,name="var1",numchild="0",value="1",type="int",thread-id="1",has_more="0"
+<- -var-delete -c var1
+-> ^done,ndeleted="1"
+<- -stack-list-frames 0 1
+-> ^done,stack=[frame={level="0",addr="0x0000000000001139",func="tick",file="main.c",fullname="/src/main.c",line="5"},frame={level="1",addr="0x0000000000001180",func="main",file="main.c",fullname="/src/main.c",line="12"}]
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x0000000000001139",func="tick",args=[],file="main.c",fullname="/src/main.c",line="5"},thread-id="1",stopped-threads="all"
+<- -var-create --thread 1 "-" "*" "counter"
+-> ^error,msg="No frame selected."
+<- -stack-list-frames 0 1
+-> ^done,stack=[frame={level="0",addr="0x0000000000001139",func="tick",file="main.c",fullname="/src/main.c",line="5"}]
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="exited-normally"
+"#,
+        )
+        .await;
+
+        let actions = BreakPointActions {
+            expressions: vec!["counter".to_string()],
+            backtrace_depth: 2,
+            auto_continue: true,
+            ..Default::default()
+        };
+        manager.set_breakpoint_actions(&session_id, "1", actions).await.unwrap();
+
+        match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::ExitedNormally))
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        let snapshots = manager.get_breakpoint_snapshots(&session_id, "1", None).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].hit, 1);
+        assert_eq!(snapshots[0].values[0].value.as_deref(), Some("1"));
+        assert_eq!(snapshots[0].backtrace.as_ref().unwrap().len(), 2);
+        assert!(snapshots[1].values[0].error.is_some());
+        let last = manager.get_breakpoint_snapshots(&session_id, "1", Some(1)).await.unwrap();
+        assert_eq!(last[0].hit, 2);
+
+        manager.set_breakpoint_actions(&session_id, "1", Default::default()).await.unwrap();
+        assert!(manager.get_handle(&session_id).await.unwrap().actions.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_breakpoint_actions_without_waiting() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x0000000000001139",func="tick",args=[],file="main.c",fullname="/src/main.c",line="5"},thread-id="1",stopped-threads="all"
+<- -var-create --thread 1 "-" "*" "counter"
+-> ^done,name="var1",numchild="0",value="1",type="int",thread-id="1",has_more="0"
+<- -var-delete -c var1
+-> ^done,ndeleted="1"
+<- -data-list-register-names
+-> ^done,register-names=["rax","rbx","rcx"]
+<- -data-list-register-values --thread 1 x 1
+-> ^done,register-values=[{number="1",value="0x2a"}]
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+!sleep 200
+-> *stopped,reason="exited-normally"
+"#,
+        )
+        .await;
+
+        let actions = BreakPointActions {
+            expressions: vec!["counter".to_string()],
+            registers: vec!["1".to_string()],
+            auto_continue: true,
+            ..Default::default()
+        };
+        manager.set_breakpoint_actions(&session_id, "1", actions).await.unwrap();
+        let outcome = manager.continue_execution(&session_id, false, false, None).await.unwrap();
+        assert!(matches!(outcome, ExecutionOutcome::Running(_)));
+
+        // Both waiters see the exit, the actions ran once
+        let (first, second) = tokio::join!(
+            manager.wait_for_stop(&session_id, None, Some(5)),
+            manager.wait_for_stop(&session_id, None, Some(5)),
+        );
+        assert_eq!(first.unwrap().reason, Some(StopReason::ExitedNormally));
+        assert_eq!(second.unwrap().reason, Some(StopReason::ExitedNormally));
+        let snapshots = manager.get_breakpoint_snapshots(&session_id, "1", None).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].values[0].value.as_deref(), Some("1"));
+        let registers = snapshots[0].registers.as_ref().unwrap();
+        assert_eq!(registers[0].name.as_deref(), Some("rbx"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_stop_reasons() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+!sleep 100
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="2",frame={addr="0x0000000000001139",func="tick",args=[]},thread-id="1",stopped-threads="all"
+!sleep 100
+-> *stopped,reason="signal-received",signal-name="SIGSEGV",signal-meaning="Segmentation fault",frame={addr="0x0000000000001139",func="tick",args=[]},thread-id="1",stopped-threads="all"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+!sleep 100
+-> *stopped,reason="exited-normally"
+<- -exec-run
+-> ^running
+!sleep 100
+!exit 0
+"#,
+        )
+        .await;
+        let reasons = Some(vec!["signal-received".to_string()]);
+
+        let unknown = Some(vec!["breakpoint-hit".to_string(), "breakpoint".to_string()]);
+        let result = manager.wait_for_stop(&session_id, unknown, Some(5)).await;
+        assert!(matches!(result, Err(AppError::InvalidArgument(_))));
+
+        // The breakpoint is skipped without resuming the target
+        manager.continue_execution(&session_id, false, false, None).await.unwrap();
+        let stop = manager.wait_for_stop(&session_id, reasons.clone(), Some(5)).await.unwrap();
+        assert_eq!(stop.reason, Some(StopReason::SignalReceived));
+        assert_eq!(stop.signal_name.as_deref(), Some("SIGSEGV"));
+        // Exits end the wait whatever the reasons, this -exec-continue fails
+        // if the wait sent one already
+        manager.continue_execution(&session_id, false, false, None).await.unwrap();
+        let stop = manager.wait_for_stop(&session_id, reasons.clone(), Some(5)).await.unwrap();
+        assert_eq!(stop.reason, Some(StopReason::ExitedNormally));
+
+        manager.start_debugging(&session_id, false, None).await.unwrap();
+        let result = manager.wait_for_stop(&session_id, reasons, Some(5)).await;
+        assert!(matches!(result, Err(AppError::GDBQuit)));
+    }
+
+    #[tokio::test]
+    async fn test_catchpoints() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -catch-throw -r "std::out_of_range"
+-> ^done,bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",regexp="std::out_of_range",thread-groups=["i1"],times="0"}
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="1",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"}]}
+<- -interpreter-exec console "catch syscall write"
+-> =breakpoint-created,bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"}
+-> ^done
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="2",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"},bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"}]}
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="2",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"},bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"}]}
+<- -interpreter-exec console "tcatch signal SIGUSR1"
+-> ^done
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="3",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"},bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"},bkpt={number="3",type="catchpoint",disp="del",enabled="y",what="SIGUSR1",catch-type="signal",thread-groups=["i1"],times="0"}]}
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="3",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"},bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"},bkpt={number="3",type="catchpoint",disp="del",enabled="y",what="SIGUSR1",catch-type="signal",thread-groups=["i1"],times="0"}]}
+<- -interpreter-exec console "catch fork"
+-> ^done
+<- -break-list
+-> ^done,BreakpointTable={nr_rows="3",nr_cols="6",hdr=[],body=[bkpt={number="1",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"},bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="\"write\"",catch-type="syscall",thread-groups=["i1"],times="0"},bkpt={number="3",type="catchpoint",disp="del",enabled="y",what="SIGUSR1",catch-type="signal",thread-groups=["i1"],times="0"}]}
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="syscall-entry",disp="keep",bkptno="2",syscall-number="1",syscall-name="write",frame={addr="0x00007ffff7e9a1e4",func="write",args=[]},thread-id="1",stopped-threads="all"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> ~"\nCatchpoint 3 (signal SIGUSR1), "
+-> *stopped,frame={addr="0x00007ffff7e3e0f2",func="raise",args=[]},thread-id="1",stopped-threads="all"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x00007ffff7ea8672",func="__cxa_throw",args=[]},thread-id="1",stopped-threads="all"
+"#,
+        )
+        .await;
+
+        let catchpoint = manager
+            .set_catchpoint(&session_id, CatchEvent::Throw, Some("std::out_of_range"), false)
+            .await
+            .unwrap();
+        assert_eq!(catchpoint.catch_type, Some(CatchEvent::Throw));
+        let catchpoint = manager
+            .set_catchpoint(&session_id, CatchEvent::Syscall, Some("write"), false)
+            .await
+            .unwrap();
+        assert_eq!(catchpoint.number, BreakPointNumber { major: 2, minor: None });
+        let catchpoint = manager
+            .set_catchpoint(&session_id, CatchEvent::Signal, Some("SIGUSR1"), true)
+            .await
+            .unwrap();
+        assert_eq!(catchpoint.display, "del");
+        // Not the last catchpoint when the command creates none
+        assert!(matches!(
+            manager.set_catchpoint(&session_id, CatchEvent::Fork, None, false).await,
+            Err(AppError::GDBError(_))
+        ));
+
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        assert_eq!(stop.reason, Some(StopReason::SyscallEntry));
+        assert_eq!(stop.catchpoint, Some(CatchEvent::Syscall));
+        assert_eq!(stop.syscall_name.as_deref(), Some("write"));
+        assert_eq!(stop.syscall_number, Some(1));
+
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        assert_eq!(stop.reason, Some(StopReason::SignalReceived));
+        assert_eq!(stop.breakpoint, Some(BreakPointNumber { major: 3, minor: None }));
+        assert_eq!(stop.catchpoint, Some(CatchEvent::Signal));
+        assert_eq!(stop.signal_name.as_deref(), Some("SIGUSR1"));
 
-        // Update session status
-        let mut sessions = self.sessions.lock().await;
-        if let Some(handle) = sessions.get_mut(session_id) {
-            handle.info.status = GDBSessionStatus::Running;
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        assert_eq!(stop.catchpoint, Some(CatchEvent::Throw));
+        assert!(
+            manager
+                .get_handle(&session_id)
+                .await
+                .unwrap()
+                .breakpoints
+                .lock()
+                .await
+                .export()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_rust_panic() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -break-insert "rust_begin_unwind"
+-> ^error,msg="Function \"rust_begin_unwind\" not defined."
+<- -break-insert "rust_panic"
+-> ^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x000055555555e6c0",func="std::panicking::rust_panic",file="library/std/src/panicking.rs",fullname="/rustc/4eb161250e340c8f48f66e2b929ef4a5bed7c181/library/std/src/panicking.rs",line="831",thread-groups=["i1"],times="0",original-location="rust_panic"}
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x000055555555e6c0",func="std::panicking::rust_panic",args=[]},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.pieces"
+-> ^error,msg="No symbol \"info\" in current context."
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.args.length"
+-> ^error,msg="No symbol \"info\" in current context."
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.file"
+-> ^error,msg="No symbol \"info\" in current context."
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.line"
+-> ^error,msg="No symbol \"info\" in current context."
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.col"
+-> ^error,msg="No symbol \"info\" in current context."
+<- -stack-list-frames
+-> ^done,stack=[frame={level="0",addr="0x000055555555e6c0",func="std::panicking::rust_panic",file="library/std/src/panicking.rs",fullname="/rustc/4eb1612/library/std/src/panicking.rs",line="831"},frame={level="1",addr="0x000055555555a2b0",func="core::panicking::panic_fmt",file="/rustc/4eb1612/library/core/src/panicking.rs",line="75"},frame={level="2",addr="0x0000555555559d10",func="demo::main",file="src/main.rs",fullname="/src/demo/src/main.rs",line="4"}]
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x000055555555e6c0",func="rust_begin_unwind",args=[]},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.pieces"
+-> ^done,value="&[&str](size=1) = {\"index out of bounds\"}"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.args.length"
+-> ^done,value="0"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.file"
+-> ^done,value="\"src/main.rs\""
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.line"
+-> ^done,value="7"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.col"
+-> ^done,value="13"
+<- -exec-continue
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x000055555555e6c0",func="rust_begin_unwind",args=[]},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.pieces"
+-> ^done,value="&[&str](size=2) = {\"index \", \" out of range for {len}\"}"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.message.args.length"
+-> ^done,value="1"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.file"
+-> ^done,value="\"src/main.rs\""
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.line"
+-> ^done,value="9"
+<- -data-evaluate-expression --thread 1 --frame 0 "info.location.col"
+-> ^done,value="5"
+<- -break-delete 1
+-> ^done
+"#,
+        )
+        .await;
+
+        let breakpoint = manager.catch_rust_panic(&session_id).await.unwrap();
+        assert_eq!(breakpoint.original_location.as_deref(), Some("rust_panic"));
+
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        let panic = stop.panic.unwrap();
+        assert_eq!(panic.message, None);
+        assert_eq!(panic.file.as_deref(), Some("src/main.rs"));
+        assert_eq!(panic.line, Some(4));
+
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        assert_eq!(
+            stop.panic,
+            Some(RustPanic {
+                message: Some("index out of bounds".to_string()),
+                message_template: None,
+                file: Some("src/main.rs".to_string()),
+                line: Some(7),
+                column: Some(13),
+            })
+        );
+
+        // Formatted arguments can't be read, only the template is reported
+        let stop =
+            match manager.continue_execution(&session_id, false, true, Some(5)).await.unwrap() {
+                ExecutionOutcome::Stopped(stop) => stop,
+                other => panic!("target did not stop: {:?}", other),
+            };
+        let panic = stop.panic.unwrap();
+        assert_eq!(panic.message, None);
+        assert_eq!(panic.message_template.as_deref(), Some("index {} out of range for {{len}}"));
+
+        manager.delete_breakpoint(&session_id, vec!["1".to_string()]).await.unwrap();
+        let handle = manager.get_handle(&session_id).await.unwrap();
+        assert!(handle.panic_breakpoints.lock().await.is_empty());
+    }
+
+    #[test]
+    fn test_panic_message_template() {
+        let pieces = |pieces: &[&str]| pieces.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(panic_message_template(&pieces(&["a ", " b"]), Some(1)), "a {} b");
+        assert_eq!(panic_message_template(&pieces(&["x = "]), Some(1)), "x = {}");
+        assert_eq!(panic_message_template(&pieces(&["", " and "]), Some(2)), "{} and {}");
+        assert_eq!(panic_message_template(&pieces(&["a ", " b"]), None), "a {} b");
+    }
+
+    #[tokio::test]
+    async fn test_finish_until_advance_jump_and_return() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-finish
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="function-finished",frame={addr="0x0000000000001180",func="main",args=[],file="main.c",fullname="/src/main.c",line="12"},gdb-result-var="$1",return-value="42",thread-id="1",stopped-threads="all"
+<- -exec-until "main.c:14"
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="location-reached",frame={addr="0x0000000000001190",func="main",args=[],file="main.c",fullname="/src/main.c",line="14"},thread-id="1",stopped-threads="all"
+<- -interpreter-exec console "advance tick"
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="location-reached",frame={addr="0x0000000000001139",func="tick",args=[],file="main.c",fullname="/src/main.c",line="5"},thread-id="1",stopped-threads="all"
+<- -exec-return "7"
+-> ^done,frame={level="0",addr="0x0000000000001194",func="main",args=[],file="main.c",fullname="/src/main.c",line="14"}
+<- -exec-jump *0x1139
+-> ^running
+-> *running,thread-id="all"
+"#,
+        )
+        .await;
+
+        match manager.finish_execution(&session_id, false, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::FunctionFinished));
+                assert_eq!(stop.return_value.as_deref(), Some("42"));
+                assert_eq!(stop.result_var.as_deref(), Some("$1"));
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        let location = BreakPointLocation::Line(Path::new("main.c"), 14);
+        match manager.until_execution(&session_id, Some(location), true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => assert_eq!(stop.frame.unwrap().line, Some(14)),
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        let location = BreakPointLocation::Function(None, "tick");
+        match manager.advance_execution(&session_id, location, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => assert_eq!(stop.frame.unwrap().function, "tick"),
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        let frame = manager.return_from_function(&session_id, Some("7")).await.unwrap();
+        assert_eq!(frame.function, "main");
+
+        let location = BreakPointLocation::Address(0x1139);
+        let outcome = manager.jump_execution(&session_id, location, false, None).await.unwrap();
+        assert!(matches!(outcome, ExecutionOutcome::Running(_)));
+    }
+
+    #[tokio::test]
+    async fn test_reverse_and_instruction_steps() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -interpreter-exec console "record full"
+-> =record-started,thread-group="i1",method="full"
+-> ^done
+<- -exec-continue --reverse
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="watchpoint-trigger",wpt={number="2",exp="*p"},value={old="0",new="7"},frame={addr="0x0000555555555149",func="corrupt",args=[],file="crash.c",fullname="/src/crash.c",line="4"},thread-id="1",stopped-threads="all"
+<- -data-disassemble -s 93824992235849 -e 93824992235850 -- 0
+-> ^done,asm_insns=[{address="0x0000555555555149",func-name="corrupt",offset="16",inst="movl   $0x7,(%rax)"}]
+<- -exec-step-instruction --reverse
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000555555555145",func="corrupt",args=[],file="crash.c",fullname="/src/crash.c",line="4"},thread-id="1",stopped-threads="all"
+<- -data-disassemble -s 93824992235845 -e 93824992235846 -- 0
+-> ^error,msg="Cannot access memory at address 0x555555555145"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000555555555150",func="corrupt",args=[],file="crash.c",fullname="/src/crash.c",line="5"},thread-id="1",stopped-threads="all"
+<- -interpreter-exec console "record stop"
+-> ^done
+"#,
+        )
+        .await;
+
+        manager.start_recording(&session_id).await.unwrap();
+        match manager.continue_execution(&session_id, true, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => {
+                assert_eq!(stop.reason, Some(StopReason::WatchpointTrigger));
+                let instruction = stop.instruction.unwrap();
+                assert_eq!(instruction.address, Address(0x555555555149));
+                assert_eq!(instruction.offset, Some(16));
+                assert_eq!(instruction.instruction, "movl   $0x7,(%rax)");
+            }
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        // Failing to disassemble doesn't fail the step
+        match manager.step_instruction(&session_id, true, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => assert!(stop.instruction.is_none()),
+            other => panic!("target did not stop: {:?}", other),
+        }
+
+        match manager.next_execution(&session_id, false, true, Some(5)).await.unwrap() {
+            ExecutionOutcome::Stopped(stop) => assert!(stop.instruction.is_none()),
+            other => panic!("target did not stop: {:?}", other),
+        }
+        manager.stop_recording(&session_id).await.unwrap();
+    }
+
+    #[test]
+    fn test_is_true() {
+        for value in ["1", "-3", "true", "0x601040", "97 'a'", "(int *) 0x601040", "1.5"] {
+            assert!(is_true(value), "{} is false", value);
+        }
+        for value in ["0", "false", "0x0", "0 '\\000'", "(char *) 0x0", "0.0"] {
+            assert!(!is_true(value), "{} is true", value);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeat_step() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="6"},thread-id="1",stopped-threads="all"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="7"},thread-id="1",stopped-threads="all"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="breakpoint-hit",disp="keep",bkptno="1",frame={addr="0x0000000000001160",func="loop",args=[],file="main.c",fullname="/src/main.c",line="8"},thread-id="1",stopped-threads="all"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="6"},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression "i == 2"
+-> ^done,value="0"
+<- -exec-next
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="7"},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression "i == 2"
+-> ^done,value="1"
+<- -stack-info-frame
+-> ^done,frame={level="0",addr="0x0000000000001150",func="loop",file="main.c",fullname="/src/main.c",line="7"}
+<- -exec-step
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="8"},thread-id="1",stopped-threads="all"
+<- -exec-step
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="tick",args=[],file="main.c",fullname="/src/main.c",line="2"},thread-id="1",stopped-threads="all"
+<- -exec-step
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="6"},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression "done"
+-> ^done,value="false"
+<- -exec-step
+-> ^running
+-> *running,thread-id="all"
+-> *stopped,reason="end-stepping-range",frame={addr="0x0000000000001150",func="loop",args=[],file="main.c",fullname="/src/main.c",line="7"},thread-id="1",stopped-threads="all"
+<- -data-evaluate-expression "done"
+-> ^done,value="false"
+"#,
+        )
+        .await;
+
+        let repeat = StepRepeat { count: Some(2), ..Default::default() };
+        let outcome =
+            manager.repeat_step(&session_id, true, false, &repeat, Some(5)).await.unwrap();
+        assert_eq!((outcome.steps, outcome.end), (2, StepEnd::Count));
+
+        // A breakpoint ends the steps early
+        let repeat = StepRepeat { count: Some(5), ..Default::default() };
+        let outcome =
+            manager.repeat_step(&session_id, true, false, &repeat, Some(5)).await.unwrap();
+        assert_eq!((outcome.steps, outcome.end), (1, StepEnd::Stopped));
+
+        let until = StepCondition::Expression("i == 2".to_string());
+        let repeat = StepRepeat { until: Some(until), ..Default::default() };
+        let outcome =
+            manager.repeat_step(&session_id, true, false, &repeat, Some(5)).await.unwrap();
+        assert_eq!((outcome.steps, outcome.end), (2, StepEnd::Condition));
+
+        let repeat =
+            StepRepeat { until: Some(StepCondition::FunctionChanged), ..Default::default() };
+        let outcome =
+            manager.repeat_step(&session_id, false, false, &repeat, Some(5)).await.unwrap();
+        assert_eq!((outcome.steps, outcome.end), (2, StepEnd::Condition));
+        match outcome.last {
+            ExecutionOutcome::Stopped(stop) => assert_eq!(stop.frame.unwrap().function, "tick"),
+            other => panic!("target did not stop: {:?}", other),
         }
 
-        Ok(response.results.to_string())
+        let until = StepCondition::Expression("done".to_string());
+        let repeat = StepRepeat { until: Some(until), max_steps: Some(2), ..Default::default() };
+        let outcome =
+            manager.repeat_step(&session_id, false, false, &repeat, Some(5)).await.unwrap();
+        assert_eq!((outcome.steps, outcome.end), (2, StepEnd::MaxSteps));
     }
 
-    /// Step execution
-    pub async fn step_execution(&self, session_id: &str) -> AppResult<String> {
-        let response = self.send_command_with_timeout(session_id, &MiCommand::exec_step()).await?;
+    #[tokio::test]
+    async fn test_threads() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -thread-info
+-> ^done,threads=[{id="1",target-id="Thread 0x7ffff7d8a740 (LWP 1234)",name="app",frame={level="0",addr="0x0000000000401136",func="main",args=[],file="main.c",fullname="/src/main.c",line="12",arch="i386:x86-64"},state="stopped",core="0"},{id="2",target-id="Thread 0x7ffff7d89640 (LWP 1235)",name="worker",frame={level="0",addr="0x0000000000401176",func="worker",args=[{name="arg",value="0x0"}],file="main.c",fullname="/src/main.c",line="5",arch="i386:x86-64"},state="stopped",core="1"}],current-thread-id="1"
+<- -thread-select 9
+-> ^error,msg="Invalid thread id: 9"
+<- -thread-select 2
+-> ^done,new-thread-id="2",frame={level="0",addr="0x0000000000401176",func="worker",args=[{name="arg",value="0x0"}],file="main.c",fullname="/src/main.c",line="5",arch="i386:x86-64"}
+<- -thread-info 2
+-> ^done,threads=[{id="2",target-id="Thread 0x7ffff7d89640 (LWP 1235)",name="worker",frame={level="0",addr="0x0000000000401176",func="worker",args=[{name="arg",value="0x0"}],file="main.c",fullname="/src/main.c",line="5",arch="i386:x86-64"},state="stopped",core="1"}]
+<- -thread-select 3
+-> ^done,new-thread-id="3"
+<- -thread-info 3
+-> ^error,msg="Thread 3 has exited"
+<- -stack-list-frames --thread 1
+-> ^done,stack=[frame={level="0",addr="0x0000000000401136",func="main",file="main.c",fullname="/src/main.c",line="12"}]
+<- -stack-list-variables --thread 1 --frame 0 --simple-values
+-> ^done,variables=[{name="worker",type="pthread_t",value="140737351554624"}]
+<- -data-list-register-names
+-> ^done,register-names=["rax","rbx","rcx"]
+<- -data-list-register-values --thread 1 x 2
+-> ^done,register-values=[{number="2",value="0x1c"}]
+"#,
+        )
+        .await;
+
+        let list = manager.list_threads(&session_id).await.unwrap();
+        assert_eq!(list.current_thread_id, Some(1));
+        assert_eq!(list.threads.len(), 2);
+        assert_eq!(list.threads[0].name.as_deref(), Some("app"));
+        assert_eq!(list.threads[1].state, ThreadState::Stopped);
+        assert_eq!(list.threads[1].core, Some(1));
+        assert_eq!(list.threads[1].frame.as_ref().unwrap().function, "worker");
+
+        assert!(matches!(manager.select_thread(&session_id, 9).await, Err(AppError::GDBError(_))));
+        let thread = manager.select_thread(&session_id, 2).await.unwrap();
+        assert_eq!(thread.id, 2);
+        assert_eq!(thread.target_id, "Thread 0x7ffff7d89640 (LWP 1235)");
+        match manager.select_thread(&session_id, 3).await {
+            Err(AppError::GDBError(msg)) => assert!(msg.contains("Thread 3 has exited")),
+            other => panic!("expected the error of GDB, got {:?}", other),
+        }
 
-        Ok(response.results.to_string())
+        let frames = manager.get_stack_frames(&session_id, Some(1)).await.unwrap();
+        assert_eq!(frames[0].function, "main");
+        let variables = manager.get_local_variables(&session_id, Some(1), Some(0)).await.unwrap();
+        assert_eq!(variables[0].name, "worker");
+        let registers =
+            manager.get_registers(&session_id, Some(1), Some(vec!["2".to_string()])).await.unwrap();
+        assert_eq!(registers[0].name.as_deref(), Some("rcx"));
     }
 
-    /// Next execution
-    pub async fn next_execution(&self, session_id: &str) -> AppResult<String> {
-        let response = self.send_command_with_timeout(session_id, &MiCommand::exec_next()).await?;
+    #[tokio::test]
+    async fn test_get_all_backtraces() {
+        let (manager, session_id) = fake_session(
+            r#"
+<- -thread-info
+-> ^done,threads=[{id="1",target-id="LWP 1",state="stopped"},{id="2",target-id="LWP 2",state="stopped"},{id="3",target-id="LWP 3",state="running"},{id="4",target-id="LWP 4",state="stopped"},{id="5",target-id="LWP 5",state="stopped"}],current-thread-id="1"
+<- -stack-list-frames --thread 1 0 1
+-> ^done,stack=[frame={level="0",addr="0x0000000000401136",func="main",file="main.c",fullname="/src/main.c",line="12"}]
+<- -stack-list-frames --thread 2 0 1
+-> ^done,stack=[frame={level="0",addr="0x00007ffff7e1a2c0",func="pthread_cond_wait"},frame={level="1",addr="0x0000000000401176",func="worker",file="main.c",fullname="/src/main.c",line="5"}]
+<- -stack-list-frames --thread 4 0 1
+-> ^error,msg="Cannot access memory at address 0x0"
+<- -stack-list-frames --thread 5 0 1
+-> ^done,stack=[frame={level="0",addr="0x00007ffff7e1a2c0",func="pthread_cond_wait"},frame={level="1",addr="0x0000000000401176",func="worker",file="main.c",fullname="/src/main.c",line="5"}]
+"#,
+        )
+        .await;
 
-        Ok(response.results.to_string())
+        let backtraces = manager.get_all_backtraces(&session_id, Some(2)).await.unwrap();
+        assert_eq!(backtraces.threads, 5);
+        assert_eq!(backtraces.groups.len(), 2);
+        assert_eq!(
+            (backtraces.groups[0].count, &backtraces.groups[0].thread_ids),
+            (2, &vec![2, 5])
+        );
+        assert_eq!(backtraces.groups[0].frames[0].function, "pthread_cond_wait");
+        assert_eq!(backtraces.groups[1].thread_ids, vec![1]);
+        assert_eq!(backtraces.running, Some(vec![3]));
+        assert!(backtraces.errors.unwrap()[0].starts_with("thread 4: "));
     }
 }
@@ -634,17 +634,60 @@ fn register_tools(builder: ServerProtocolBuilder) -> ServerProtocolBuilder {
         .register_tool(tools::CreateSessionTool::tool(), tools::CreateSessionTool::call())
         .register_tool(tools::GetSessionTool::tool(), tools::GetSessionTool::call())
         .register_tool(tools::GetAllSessionsTool::tool(), tools::GetAllSessionsTool::call())
+        .register_tool(tools::GetSessionEventsTool::tool(), tools::GetSessionEventsTool::call())
         .register_tool(tools::CloseSessionTool::tool(), tools::CloseSessionTool::call())
         .register_tool(tools::StartDebuggingTool::tool(), tools::StartDebuggingTool::call())
         .register_tool(tools::StopDebuggingTool::tool(), tools::StopDebuggingTool::call())
+        .register_tool(tools::WaitForStopTool::tool(), tools::WaitForStopTool::call())
         .register_tool(tools::GetBreakpointsTool::tool(), tools::GetBreakpointsTool::call())
         .register_tool(tools::SetBreakpointTool::tool(), tools::SetBreakpointTool::call())
+        .register_tool(tools::SetWatchpointTool::tool(), tools::SetWatchpointTool::call())
+        .register_tool(tools::SetCatchpointTool::tool(), tools::SetCatchpointTool::call())
+        .register_tool(tools::CatchRustPanicTool::tool(), tools::CatchRustPanicTool::call())
+        .register_tool(tools::SetLogpointTool::tool(), tools::SetLogpointTool::call())
+        .register_tool(tools::GetLogpointOutputTool::tool(), tools::GetLogpointOutputTool::call())
         .register_tool(tools::DeleteBreakpointTool::tool(), tools::DeleteBreakpointTool::call())
+        .register_tool(tools::EnableBreakpointsTool::tool(), tools::EnableBreakpointsTool::call())
+        .register_tool(tools::DisableBreakpointsTool::tool(), tools::DisableBreakpointsTool::call())
+        .register_tool(
+            tools::SetBreakpointConditionTool::tool(),
+            tools::SetBreakpointConditionTool::call(),
+        )
+        .register_tool(
+            tools::SetBreakpointIgnoreCountTool::tool(),
+            tools::SetBreakpointIgnoreCountTool::call(),
+        )
+        .register_tool(
+            tools::SetBreakpointActionsTool::tool(),
+            tools::SetBreakpointActionsTool::call(),
+        )
+        .register_tool(
+            tools::GetBreakpointSnapshotsTool::tool(),
+            tools::GetBreakpointSnapshotsTool::call(),
+        )
+        .register_tool(tools::ExportBreakpointsTool::tool(), tools::ExportBreakpointsTool::call())
+        .register_tool(tools::ImportBreakpointsTool::tool(), tools::ImportBreakpointsTool::call())
+        .register_tool(tools::ListThreadsTool::tool(), tools::ListThreadsTool::call())
+        .register_tool(tools::SelectThreadTool::tool(), tools::SelectThreadTool::call())
         .register_tool(tools::GetStackFramesTool::tool(), tools::GetStackFramesTool::call())
+        .register_tool(tools::GetAllBacktracesTool::tool(), tools::GetAllBacktracesTool::call())
         .register_tool(tools::GetLocalVariablesTool::tool(), tools::GetLocalVariablesTool::call())
+        .register_tool(
+            tools::EvaluateExpressionTool::tool(),
+            tools::EvaluateExpressionTool::call(),
+        )
         .register_tool(tools::ContinueExecutionTool::tool(), tools::ContinueExecutionTool::call())
         .register_tool(tools::StepExecutionTool::tool(), tools::StepExecutionTool::call())
         .register_tool(tools::NextExecutionTool::tool(), tools::NextExecutionTool::call())
+        .register_tool(tools::FinishExecutionTool::tool(), tools::FinishExecutionTool::call())
+        .register_tool(tools::StepInstructionTool::tool(), tools::StepInstructionTool::call())
+        .register_tool(tools::NextInstructionTool::tool(), tools::NextInstructionTool::call())
+        .register_tool(tools::StartRecordingTool::tool(), tools::StartRecordingTool::call())
+        .register_tool(tools::StopRecordingTool::tool(), tools::StopRecordingTool::call())
+        .register_tool(tools::UntilExecutionTool::tool(), tools::UntilExecutionTool::call())
+        .register_tool(tools::AdvanceExecutionTool::tool(), tools::AdvanceExecutionTool::call())
+        .register_tool(tools::JumpExecutionTool::tool(), tools::JumpExecutionTool::call())
+        .register_tool(tools::ReturnFromFunctionTool::tool(), tools::ReturnFromFunctionTool::call())
         .register_tool(tools::GetRegistersTool::tool(), tools::GetRegistersTool::call())
         .register_tool(tools::GetRegisterNamesTool::tool(), tools::GetRegisterNamesTool::call())
         .register_tool(tools::ReadMemoryTool::tool(), tools::ReadMemoryTool::call())
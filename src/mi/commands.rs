@@ -26,12 +26,94 @@ pub enum DisassembleMode {
                                                   * as above */
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WatchMode {
     Read,
     Write,
     Access,
 }
 
+impl FromStr for WatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "read" | "r" => WatchMode::Read,
+            "write" | "w" => WatchMode::Write,
+            "access" | "a" => WatchMode::Access,
+            _ => return Err(format!("Invalid watch mode: {}", s)),
+        })
+    }
+}
+
+impl fmt::Display for WatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchMode::Read => write!(f, "read"),
+            WatchMode::Write => write!(f, "write"),
+            WatchMode::Access => write!(f, "access"),
+        }
+    }
+}
+
+/// Event a catchpoint stops at, named as in the `catch-type` of GDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchEvent {
+    /// A C++ exception is thrown
+    Throw,
+    /// A C++ exception is caught
+    Catch,
+    /// A C++ exception is rethrown
+    Rethrow,
+    /// A system call is entered or returns
+    Syscall,
+    Fork,
+    Vfork,
+    Exec,
+    /// A signal is received
+    Signal,
+}
+
+impl CatchEvent {
+    /// Exception events have MI commands, the others only exist in the CLI
+    pub fn is_exception(&self) -> bool {
+        matches!(self, CatchEvent::Throw | CatchEvent::Catch | CatchEvent::Rethrow)
+    }
+}
+
+impl FromStr for CatchEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "throw" => CatchEvent::Throw,
+            "catch" => CatchEvent::Catch,
+            "rethrow" => CatchEvent::Rethrow,
+            "syscall" => CatchEvent::Syscall,
+            "fork" => CatchEvent::Fork,
+            "vfork" => CatchEvent::Vfork,
+            "exec" => CatchEvent::Exec,
+            "signal" => CatchEvent::Signal,
+            _ => return Err(format!("Invalid catch event: {}", s)),
+        })
+    }
+}
+
+impl fmt::Display for CatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatchEvent::Throw => write!(f, "throw"),
+            CatchEvent::Catch => write!(f, "catch"),
+            CatchEvent::Rethrow => write!(f, "rethrow"),
+            CatchEvent::Syscall => write!(f, "syscall"),
+            CatchEvent::Fork => write!(f, "fork"),
+            CatchEvent::Vfork => write!(f, "vfork"),
+            CatchEvent::Exec => write!(f, "exec"),
+            CatchEvent::Signal => write!(f, "signal"),
+        }
+    }
+}
+
 /// Register format
 pub enum RegisterFormat {
     Binary,
@@ -71,10 +153,149 @@ impl fmt::Display for RegisterFormat {
     }
 }
 
+/// Output format of the value of a variable object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueFormat {
+    Binary,
+    Decimal,
+    Hexadecimal,
+    Octal,
+    Natural,
+    ZeroHexadecimal,
+}
+
+impl FromStr for ValueFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "binary" | "b" => ValueFormat::Binary,
+            "decimal" | "d" => ValueFormat::Decimal,
+            "hexadecimal" | "hex" | "x" => ValueFormat::Hexadecimal,
+            "octal" | "o" => ValueFormat::Octal,
+            "natural" | "N" => ValueFormat::Natural,
+            "zero-hexadecimal" | "z" => ValueFormat::ZeroHexadecimal,
+            _ => return Err(format!("Invalid value format: {}", s)),
+        })
+    }
+}
+
+impl fmt::Display for ValueFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValueFormat::Binary => write!(f, "binary"),
+            ValueFormat::Decimal => write!(f, "decimal"),
+            ValueFormat::Hexadecimal => write!(f, "hexadecimal"),
+            ValueFormat::Octal => write!(f, "octal"),
+            ValueFormat::Natural => write!(f, "natural"),
+            ValueFormat::ZeroHexadecimal => write!(f, "zero-hexadecimal"),
+        }
+    }
+}
+
 pub enum BreakPointLocation<'a> {
     Address(usize),
-    Function(&'a Path, &'a str),
+    /// A function, optionally only the one of a source file
+    Function(Option<&'a Path>, &'a str),
     Line(&'a Path, usize),
+    /// A location in the syntax of GDB, e.g. main.c:5 as reported in the
+    /// original-location of a breakpoint
+    Linespec(&'a str),
+}
+
+/// Options of `-break-insert`, the defaults insert a plain breakpoint
+#[derive(Debug, Clone, Default)]
+pub struct BreakPointOptions {
+    /// Only stop if this expression is true
+    pub condition: Option<String>,
+    /// Number of hits to ignore before stopping
+    pub ignore_count: Option<usize>,
+    /// Delete the breakpoint after the first hit
+    pub temporary: bool,
+    /// Insert a hardware breakpoint
+    pub hardware: bool,
+    /// Keep the breakpoint pending if the location can't be resolved yet,
+    /// e.g. in a shared library not loaded yet
+    pub pending: bool,
+    /// Only stop in this thread
+    pub thread: Option<usize>,
+}
+
+impl BreakPointLocation<'_> {
+    /// The location argument of `-break-insert`, quoted as it may come from
+    /// the user and contain anything
+    fn to_arg(&self) -> OsString {
+        match self {
+            BreakPointLocation::Address(_) => self.to_linespec(),
+            _ => OsString::from(escape_command(&self.to_linespec().to_string_lossy())),
+        }
+    }
+
+    /// The location in the syntax of GDB, unquoted
+    fn to_linespec(&self) -> OsString {
+        match *self {
+            BreakPointLocation::Address(addr) => OsString::from(format!("*0x{:x}", addr)),
+            BreakPointLocation::Function(None, func_name) => OsString::from(func_name),
+            BreakPointLocation::Function(Some(path), func_name) => {
+                let mut ret = OsString::from(path);
+                ret.push(":");
+                ret.push(func_name);
+                ret
+
+                // Not available in old gdb(mi) versions
+                //vec![
+                //    OsString::from("--source"),
+                //    OsString::from(path),
+                //    OsString::from("--function"),
+                //    OsString::from(func_name),
+                //]
+            }
+            BreakPointLocation::Line(path, line_number) => {
+                let mut ret = OsString::from(path);
+                ret.push(":");
+                ret.push(line_number.to_string());
+                ret
+
+                // Not available in old gdb(mi) versions
+                //vec![
+                //OsString::from("--source"),
+                //OsString::from(path),
+                //OsString::from("--line"),
+                //OsString::from(format!("{}", line_number)),
+                //],
+            }
+            BreakPointLocation::Linespec(linespec) => OsString::from(linespec),
+        }
+    }
+}
+
+impl BreakPointOptions {
+    /// The options of `-break-insert` and `-dprintf-insert`
+    fn to_args(&self) -> Vec<OsString> {
+        let mut options = vec![];
+        if self.temporary {
+            options.push("-t".into());
+        }
+        if self.hardware {
+            options.push("-h".into());
+        }
+        if self.pending {
+            options.push("-f".into());
+        }
+        if let Some(condition) = &self.condition {
+            options.push("-c".into());
+            options.push(escape_command(condition).into());
+        }
+        if let Some(ignore_count) = self.ignore_count {
+            options.push("-i".into());
+            options.push(ignore_count.to_string().into());
+        }
+        if let Some(thread) = self.thread {
+            options.push("-p".into());
+            options.push(thread.to_string().into());
+        }
+        options
+    }
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize)]
@@ -83,23 +304,26 @@ pub struct BreakPointNumber {
     pub minor: Option<usize>,
 }
 
+impl FromStr for BreakPointNumber {
+    type Err = std::num::ParseIntError;
+
+    /// Parse `major` or `major.minor`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((major, minor)) = s.split_once('.') {
+            Ok(BreakPointNumber { major: major.parse()?, minor: Some(minor.parse()?) })
+        } else {
+            Ok(BreakPointNumber { major: s.parse()?, minor: None })
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for BreakPointNumber {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
         let s: String = serde::Deserialize::deserialize(deserializer)?;
-        if let Some(dot_pos) = s.find('.') {
-            Ok(BreakPointNumber {
-                major: s[..dot_pos].parse::<usize>().map_err(de::Error::custom)?,
-                minor: Some(s[dot_pos + 1..].parse::<usize>().map_err(de::Error::custom)?),
-            })
-        } else {
-            Ok(BreakPointNumber {
-                major: s.parse::<usize>().map_err(de::Error::custom)?,
-                minor: None,
-            })
-        }
+        s.parse().map_err(de::Error::custom)
     }
 }
 
@@ -119,8 +343,9 @@ fn escape_command(input: &str) -> String {
         match c {
             '\\' => output.push_str("\\\\"),
             '\"' => output.push_str("\\\""),
-            '\r' => output.push_str("\\\r"),
-            '\n' => output.push_str("\\\n"),
+            '\r' => output.push_str("\\r"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
             other => output.push(other),
         }
     }
@@ -128,12 +353,36 @@ fn escape_command(input: &str) -> String {
     output
 }
 
+/// The `--thread` and `--frame` options selecting the context of a command
+fn thread_and_frame(thread_number: Option<usize>, frame_number: Option<usize>) -> Vec<OsString> {
+    let mut options = vec![];
+    if let Some(thread_number) = thread_number {
+        options.push("--thread".into());
+        options.push(thread_number.to_string().into());
+    }
+    if let Some(frame_number) = frame_number {
+        options.push("--frame".into());
+        options.push(frame_number.to_string().into());
+    }
+    options
+}
+
 impl MiCommand {
     pub async fn write_interpreter_string<S: AsyncWriteExt + Unpin>(
         &self,
         sink: &mut S,
         token: u64,
     ) -> Result<(), Error> {
+        let mut command = self.interpreter_string(token);
+        command.push("\n");
+        info!("Writing GDB command: {}", String::from_utf8_lossy(command.as_encoded_bytes()));
+
+        sink.write_all(command.as_encoded_bytes()).await?;
+        Ok(())
+    }
+
+    /// The command line sent to GDB, without the line ending
+    pub fn interpreter_string(&self, token: u64) -> OsString {
         // use std::os::unix::ffi::OsStrExt;
         let mut command = OsString::new();
         if !self.operation.is_empty() {
@@ -155,11 +404,7 @@ impl MiCommand {
                 command.push(parameter);
             }
         }
-        command.push("\n");
-        info!("Writing GDB command: {}", String::from_utf8_lossy(command.as_encoded_bytes()));
-
-        sink.write_all(command.as_encoded_bytes()).await?;
-        Ok(())
+        command
     }
 
     pub fn interpreter_exec<S1: Into<OsString>, S2: Into<OsString>>(
@@ -214,52 +459,43 @@ impl MiCommand {
         }
     }
 
-    pub fn data_evaluate_expression(expression: String) -> MiCommand {
+    pub fn data_evaluate_expression(
+        expression: &str,
+        thread_number: Option<usize>,
+        frame_number: Option<usize>,
+    ) -> MiCommand {
+        let mut parameters = thread_and_frame(thread_number, frame_number);
+        parameters.push(escape_command(expression).into());
         MiCommand {
             operation: "data-evaluate-expression",
-            options: Some(vec![OsString::from(format!("\"{}\"", expression))]), /* TODO: maybe we need to quote existing " in expression. Is this even possible? */
-            parameters: None,
+            options: None,
+            parameters: Some(parameters),
         }
     }
 
-    pub fn insert_breakpoint(location: BreakPointLocation) -> MiCommand {
-        MiCommand {
-            operation: "break-insert",
-            options: match location {
-                BreakPointLocation::Address(addr) => {
-                    Some(vec![OsString::from(format!("*0x{:x}", addr))])
-                }
-                BreakPointLocation::Function(path, func_name) => {
-                    let mut ret = OsString::from(path);
-                    ret.push(":");
-                    ret.push(func_name);
-                    Some(vec![ret])
-
-                    // Not available in old gdb(mi) versions
-                    //vec![
-                    //    OsString::from("--source"),
-                    //    OsString::from(path),
-                    //    OsString::from("--function"),
-                    //    OsString::from(func_name),
-                    //]
-                }
-                BreakPointLocation::Line(path, line_number) => {
-                    let mut ret = OsString::from(path);
-                    ret.push(":");
-                    ret.push(line_number.to_string());
-                    Some(vec![ret])
-
-                    // Not available in old gdb(mi) versions
-                    //vec![
-                    //OsString::from("--source"),
-                    //OsString::from(path),
-                    //OsString::from("--line"),
-                    //OsString::from(format!("{}", line_number)),
-                    //],
-                }
-            },
-            parameters: None,
-        }
+    pub fn insert_breakpoint(
+        location: BreakPointLocation,
+        breakpoint_options: &BreakPointOptions,
+    ) -> MiCommand {
+        let mut options = breakpoint_options.to_args();
+        options.push(location.to_arg());
+        MiCommand { operation: "break-insert", options: Some(options), parameters: None }
+    }
+
+    /// Insert a dynamic printf, printing `format` with `arguments` like
+    /// printf when hit and continuing
+    pub fn insert_dprintf(
+        location: BreakPointLocation,
+        breakpoint_options: &BreakPointOptions,
+        format: &str,
+        arguments: &[String],
+    ) -> MiCommand {
+        let mut options = breakpoint_options.to_args();
+        options.push(location.to_arg());
+        options.push(escape_command(format).into());
+        // Quoted, GDB takes every argument as a single expression
+        options.extend(arguments.iter().map(|arg| escape_command(arg).into()));
+        MiCommand { operation: "dprintf-insert", options: Some(options), parameters: None }
     }
 
     pub fn delete_breakpoints(breakpoint_numbers: Vec<BreakPointNumber>) -> MiCommand {
@@ -274,6 +510,76 @@ impl MiCommand {
         }
     }
 
+    pub fn enable_breakpoints(breakpoint_numbers: &[BreakPointNumber]) -> MiCommand {
+        MiCommand {
+            operation: "break-enable",
+            options: Some(breakpoint_numbers.iter().map(|n| n.to_string().into()).collect()),
+            parameters: None,
+        }
+    }
+
+    pub fn disable_breakpoints(breakpoint_numbers: &[BreakPointNumber]) -> MiCommand {
+        MiCommand {
+            operation: "break-disable",
+            options: Some(breakpoint_numbers.iter().map(|n| n.to_string().into()).collect()),
+            parameters: None,
+        }
+    }
+
+    /// Make the breakpoint conditional, or unconditional without a condition
+    pub fn breakpoint_condition(
+        breakpoint_number: BreakPointNumber,
+        condition: Option<&str>,
+    ) -> MiCommand {
+        let mut options = vec![breakpoint_number.to_string().into()];
+        if let Some(condition) = condition {
+            options.push(escape_command(condition).into());
+        }
+        MiCommand { operation: "break-condition", options: Some(options), parameters: None }
+    }
+
+    /// Ignore the next `count` hits of the breakpoint
+    pub fn breakpoint_after(breakpoint_number: BreakPointNumber, count: usize) -> MiCommand {
+        MiCommand {
+            operation: "break-after",
+            options: Some(vec![breakpoint_number.to_string().into(), count.to_string().into()]),
+            parameters: None,
+        }
+    }
+
+    /// Insert a catchpoint. `filter` is a regex on the exception type for
+    /// exception events, the system calls or signals to catch for syscall
+    /// and signal events, separated by spaces.
+    pub fn insert_catchpoint(
+        event: CatchEvent,
+        filter: Option<&str>,
+        temporary: bool,
+    ) -> MiCommand {
+        if !event.is_exception() {
+            let catch = if temporary { "tcatch" } else { "catch" };
+            let command = match filter {
+                Some(filter) => format!("{} {} {}", catch, event, filter),
+                None => format!("{} {}", catch, event),
+            };
+            return MiCommand::cli_exec(&command);
+        }
+
+        let mut options = vec![];
+        if temporary {
+            options.push("-t".into());
+        }
+        if let Some(filter) = filter {
+            options.push("-r".into());
+            options.push(escape_command(filter).into());
+        }
+        let operation = match event {
+            CatchEvent::Catch => "catch-catch",
+            CatchEvent::Rethrow => "catch-rethrow",
+            _ => "catch-throw",
+        };
+        MiCommand { operation, options: Some(options), parameters: None }
+    }
+
     pub fn breakpoints_list() -> MiCommand {
         MiCommand { operation: "break-list", ..Default::default() }
     }
@@ -284,7 +590,17 @@ impl MiCommand {
             WatchMode::Read => Some(vec!["-r".into()]),
             WatchMode::Access => Some(vec!["-a".into()]),
         };
-        MiCommand { operation: "break-watch", options, parameters: Some(vec![expression.into()]) }
+        // Quoted, GDB takes the expression as a single argument
+        MiCommand {
+            operation: "break-watch",
+            options,
+            parameters: Some(vec![escape_command(expression).into()]),
+        }
+    }
+
+    /// List the features supported by GDB/MI, cheap enough to probe GDB
+    pub fn list_features() -> MiCommand {
+        MiCommand { operation: "list-features", ..Default::default() }
     }
 
     pub fn environment_pwd() -> MiCommand {
@@ -313,6 +629,72 @@ impl MiCommand {
         MiCommand { operation: "exec-next", ..Default::default() }
     }
 
+    /// Step one machine instruction, into calls
+    pub fn exec_step_instruction() -> MiCommand {
+        MiCommand { operation: "exec-step-instruction", ..Default::default() }
+    }
+
+    /// Step one machine instruction, over calls
+    pub fn exec_next_instruction() -> MiCommand {
+        MiCommand { operation: "exec-next-instruction", ..Default::default() }
+    }
+
+    /// Run an execution command backwards through the recorded history
+    pub fn reverse(mut self) -> MiCommand {
+        self.options.get_or_insert_with(Vec::new).push("--reverse".into());
+        self
+    }
+
+    /// Start recording the execution, needed to run backwards. There is no
+    /// MI command for it.
+    pub fn record_start() -> MiCommand {
+        MiCommand::cli_exec("record full")
+    }
+
+    /// Stop recording the execution and drop the recorded history
+    pub fn record_stop() -> MiCommand {
+        MiCommand::cli_exec("record stop")
+    }
+
+    /// Run until the selected frame returns
+    pub fn exec_finish() -> MiCommand {
+        MiCommand { operation: "exec-finish", ..Default::default() }
+    }
+
+    /// Run until a line past the current one or, if given, until `location`
+    /// or the selected frame returns
+    pub fn exec_until(location: Option<BreakPointLocation>) -> MiCommand {
+        MiCommand {
+            operation: "exec-until",
+            options: location.map(|location| vec![location.to_arg()]),
+            parameters: None,
+        }
+    }
+
+    /// Run until `location` or the selected frame returns, there is no MI
+    /// command for it
+    pub fn exec_advance(location: BreakPointLocation) -> MiCommand {
+        MiCommand::cli_exec(&format!("advance {}", location.to_linespec().to_string_lossy()))
+    }
+
+    /// Resume at `location`
+    pub fn exec_jump(location: BreakPointLocation) -> MiCommand {
+        MiCommand {
+            operation: "exec-jump",
+            options: Some(vec![location.to_arg()]),
+            parameters: None,
+        }
+    }
+
+    /// Return from the selected frame right away, with `value` if given
+    pub fn exec_return(value: Option<&str>) -> MiCommand {
+        MiCommand {
+            operation: "exec-return",
+            options: value.map(|value| vec![escape_command(value).into()]),
+            parameters: None,
+        }
+    }
+
     // Warning: This cannot be used to pass special characters like \n to gdb
     // because (unlike it is said in the spec) there is apparently no way to
     // pass \n unescaped to gdb, and for "exec-arguments" gdb somehow does not
@@ -354,15 +736,7 @@ impl MiCommand {
         frame_number: Option<usize>,
         print_values: Option<PrintValue>,
     ) -> MiCommand {
-        let mut parameters = vec![];
-        if let Some(thread_number) = thread_number {
-            parameters.push("--thread".into());
-            parameters.push(thread_number.to_string().into());
-        }
-        if let Some(frame_number) = frame_number {
-            parameters.push("--frame".into());
-            parameters.push(frame_number.to_string().into());
-        }
+        let mut parameters = thread_and_frame(thread_number, frame_number);
         if let Some(values) = print_values {
             parameters.push(values.to_string().into());
         } else {
@@ -371,25 +745,29 @@ impl MiCommand {
         MiCommand { operation: "stack-list-variables", options: None, parameters: Some(parameters) }
     }
 
-    pub fn stack_list_frames(low_frame: Option<usize>, high_frame: Option<usize>) -> MiCommand {
-        let options = if let Some(low) = low_frame {
-            if let Some(high) = high_frame {
-                if low > high {
-                    Some(vec![high.to_string().into(), low.to_string().into()])
-                } else {
-                    Some(vec![low.to_string().into(), high.to_string().into()])
-                }
-            } else {
+    pub fn stack_list_frames(
+        thread_number: Option<usize>,
+        low_frame: Option<usize>,
+        high_frame: Option<usize>,
+    ) -> MiCommand {
+        let mut options = thread_and_frame(thread_number, None);
+        match (low_frame, high_frame) {
+            (Some(low), Some(high)) => {
+                options.push(low.min(high).to_string().into());
+                options.push(low.max(high).to_string().into());
+            }
+            (Some(low), None) => {
+                options.push(low.to_string().into());
                 // large enough number to include all frames, only existing frames will be shown
-                Some(vec![low.to_string().into(), String::from("99999").into()])
+                options.push("99999".into());
             }
-        } else {
-            if let Some(high) = high_frame {
-                Some(vec![String::from("0").into(), high.to_string().into()])
-            } else {
-                None
+            (None, Some(high)) => {
+                options.push("0".into());
+                options.push(high.to_string().into());
             }
-        };
+            (None, None) => {}
+        }
+        let options = if options.is_empty() { None } else { Some(options) };
         MiCommand { operation: "stack-list-frames", options, parameters: None }
     }
 
@@ -405,6 +783,14 @@ impl MiCommand {
         }
     }
 
+    pub fn thread_select(thread_id: usize) -> MiCommand {
+        MiCommand {
+            operation: "thread-select",
+            options: Some(vec![thread_id.to_string().into()]),
+            parameters: None,
+        }
+    }
+
     pub fn file_exec_and_symbols(file: &Path) -> MiCommand {
         MiCommand {
             operation: "file-exec-and-symbols",
@@ -437,15 +823,32 @@ impl MiCommand {
         name: Option<OsString>, /* none: generate name */
         expression: &str,
         frame_addr: Option<u64>, /* none: current frame */
+        thread_number: Option<usize>,
+        frame_number: Option<usize>,
     ) -> MiCommand {
+        let mut parameters = thread_and_frame(thread_number, frame_number);
+        parameters.extend([
+            name.unwrap_or_else(|| "\"-\"".into()),
+            frame_addr.map(|s| s.to_string()).unwrap_or_else(|| "\"*\"".to_string()).into(),
+            escape_command(expression).into(),
+        ]);
+        MiCommand { operation: "var-create", options: None, parameters: Some(parameters) }
+    }
+
+    pub fn var_evaluate_expression(
+        name: impl Into<OsString>,
+        format: Option<ValueFormat>,
+    ) -> MiCommand {
+        let mut parameters = vec![];
+        if let Some(format) = format {
+            parameters.push("-f".into());
+            parameters.push(format.to_string().into());
+        }
+        parameters.push(name.into());
         MiCommand {
-            operation: "var-create",
+            operation: "var-evaluate-expression",
             options: None,
-            parameters: Some(vec![
-                name.unwrap_or_else(|| "\"-\"".into()),
-                frame_addr.map(|s| s.to_string()).unwrap_or_else(|| "\"*\"".to_string()).into(),
-                escape_command(expression).into(),
-            ]),
+            parameters: Some(parameters),
         }
     }
 
@@ -493,20 +896,15 @@ impl MiCommand {
     /// fmt: "x": hex, "d": decimal, "o": octal, "r": raw, "N": natural
     pub fn data_list_register_values(
         fmt: RegisterFormat,
+        thread_number: Option<usize>,
         reg_list: Option<Vec<usize>>,
     ) -> MiCommand {
+        let mut options = thread_and_frame(thread_number, None);
+        options.push(fmt.to_string().into());
+        options.extend(reg_list.iter().flatten().map(|x| x.to_string().into()));
         MiCommand {
             operation: "data-list-register-values",
-            options: if let Some(list) = &reg_list {
-                Some(
-                    vec![fmt.to_string().into()]
-                        .into_iter()
-                        .chain(list.iter().map(|x| x.to_string().into()))
-                        .collect(),
-                )
-            } else {
-                Some(vec![fmt.to_string().into()])
-            },
+            options: Some(options),
             parameters: None,
         }
     }
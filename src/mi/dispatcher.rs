@@ -0,0 +1,145 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use crate::error::{AppError, AppResult};
+use crate::mi::output::ResultRecord;
+
+/// Routes result records read from GDB back to the command that issued them.
+///
+/// Every command registers its token before being written to GDB, the reader
+/// task then hands each `ResultRecord` to the matching waiter, so several
+/// commands can be in flight at once and may complete in any order.
+#[derive(Default)]
+pub struct Dispatcher {
+    state: Mutex<DispatcherState>,
+}
+
+#[derive(Default)]
+struct DispatcherState {
+    /// Waiters keyed by command token
+    pending: HashMap<u64, oneshot::Sender<ResultRecord>>,
+    /// Waiters for records without a token, served in FIFO order
+    untagged: VecDeque<oneshot::Sender<ResultRecord>>,
+    /// Tokens of commands given up before their result arrived
+    abandoned: HashSet<u64>,
+    /// Set once the reader has stopped, no more records will arrive
+    closed: bool,
+}
+
+impl Dispatcher {
+    /// Register a waiter for the result of the command with `token`
+    pub fn register(&self, token: u64) -> AppResult<oneshot::Receiver<ResultRecord>> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(AppError::GDBQuit);
+        }
+        let (sender, receiver) = oneshot::channel();
+        state.pending.insert(token, sender);
+        Ok(receiver)
+    }
+
+    /// Register a waiter for the next result without a token
+    pub fn register_untagged(&self) -> AppResult<oneshot::Receiver<ResultRecord>> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(AppError::GDBQuit);
+        }
+        let (sender, receiver) = oneshot::channel();
+        state.untagged.push_back(sender);
+        Ok(receiver)
+    }
+
+    /// Give up waiting for the result of `token`, e.g. on timeout. The late
+    /// result is drained and discarded when it eventually arrives.
+    pub fn abandon(&self, token: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.remove(&token).is_some() {
+            state.abandoned.insert(token);
+        }
+    }
+
+    /// Number of abandoned commands whose result has not arrived yet
+    pub fn abandoned_count(&self) -> usize {
+        self.state.lock().unwrap().abandoned.len()
+    }
+
+    /// Hand a result record to the command waiting for it
+    pub fn dispatch(&self, record: ResultRecord) {
+        let sender = {
+            let mut state = self.state.lock().unwrap();
+            match record.token {
+                Some(token) if state.abandoned.remove(&token) => {
+                    debug!("Discarding late result of abandoned command {}", token);
+                    return;
+                }
+                Some(token) => state.pending.remove(&token),
+                None => state.untagged.pop_front(),
+            }
+        };
+        match sender {
+            Some(sender) => {
+                if let Err(record) = sender.send(record) {
+                    warn!("Result receiver dropped, discarding {:?}", record);
+                }
+            }
+            None => warn!("No command waiting for result {:?}, discarding", record),
+        }
+    }
+
+    /// Stop accepting commands and fail all the pending ones
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.pending.clear();
+        state.untagged.clear();
+        state.abandoned.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::mi::output::ResultClass;
+
+    fn record(token: Option<u64>) -> ResultRecord {
+        ResultRecord { token, class: ResultClass::Done, results: Value::Null }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_results() {
+        let dispatcher = Dispatcher::default();
+        let first = dispatcher.register(1).unwrap();
+        let second = dispatcher.register(2).unwrap();
+        dispatcher.dispatch(record(Some(2)));
+        dispatcher.dispatch(record(Some(1)));
+        assert_eq!(second.await.unwrap().token, Some(2));
+        assert_eq!(first.await.unwrap().token, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_result_is_discarded() {
+        let dispatcher = Dispatcher::default();
+        let _abandoned = dispatcher.register(1).unwrap();
+        dispatcher.abandon(1);
+        assert_eq!(dispatcher.abandoned_count(), 1);
+        let next = dispatcher.register(2).unwrap();
+        dispatcher.dispatch(record(Some(1)));
+        dispatcher.dispatch(record(Some(2)));
+        assert_eq!(next.await.unwrap().token, Some(2));
+        assert_eq!(dispatcher.abandoned_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_close_fails_pending() {
+        let dispatcher = Dispatcher::default();
+        let pending = dispatcher.register(1).unwrap();
+        dispatcher.close();
+        assert!(pending.await.is_err());
+        assert!(matches!(dispatcher.register(2), Err(AppError::GDBQuit)));
+    }
+}
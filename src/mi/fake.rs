@@ -0,0 +1,308 @@
+//! A scripted stand-in for GDB, to exercise the server without a debugger.
+//!
+//! It runs in process and is selected with a GDB path of the form
+//! `fake-gdb:<script>`. The script is a transcript of the expected commands
+//! and the output to answer them with, one step per line:
+//!
+//! ```text
+//! # comment
+//! -> =thread-group-added,id="i1"     output, before the first command it is the banner
+//! <- -break-insert main.c:5          next expected command, without its token
+//! -> ^done,bkpt={number="1"}         result records get the token of the command
+//! <- -exec-run
+//! -> ^running
+//! !sleep 100                         pause for 100 milliseconds
+//! -> *stopped,reason="breakpoint-hit",bkptno="1"
+//! !stderr warning: something         write a line to stderr
+//! !exit 1                            exit with status 1
+//! ```
+//!
+//! An expected command ending with `*` matches every command with that
+//! prefix. Unscripted empty commands are answered with `^done` and
+//! `-gdb-exit` with `^exit`, any other unexpected command with `^error`.
+//!
+//! Recorded transcripts are played back by the fake too, see [`transcript`].
+//!
+//! [`transcript`]: crate::mi::transcript
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::debug;
+
+use crate::error::{AppError, AppResult};
+use crate::mi::transcript::{Direction, TranscriptEntry};
+
+/// Prefix of the GDB path selecting the fake, followed by the script path
+pub const FAKE_GDB_PREFIX: &str = "fake-gdb:";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Wait for this command
+    Expect(String),
+    /// Write this line to stdout
+    Output(String),
+    /// Write this line to stderr
+    Stderr(String),
+    /// Pause for milliseconds
+    Sleep(u64),
+    /// Exit with this status
+    Exit(i32),
+}
+
+/// A transcript the fake plays back
+#[derive(Debug, Clone)]
+pub struct Script {
+    steps: VecDeque<Step>,
+}
+
+impl FromStr for Script {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = VecDeque::new();
+        for (number, line) in s.lines().enumerate() {
+            let invalid = |reason: &str| {
+                AppError::InvalidArgument(format!(
+                    "line {} of fake GDB script {}",
+                    number + 1,
+                    reason
+                ))
+            };
+            let line = line.trim_end();
+            if line.trim_start().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let step = if let Some(command) = line.strip_prefix("<- ") {
+                Step::Expect(command.trim().to_string())
+            } else if let Some(output) = line.strip_prefix("->") {
+                Step::Output(output.strip_prefix(' ').unwrap_or(output).to_string())
+            } else if let Some(text) = line.strip_prefix("!stderr ") {
+                Step::Stderr(text.to_string())
+            } else if let Some(millis) = line.strip_prefix("!sleep ") {
+                Step::Sleep(millis.trim().parse().map_err(|_| invalid("has an invalid sleep"))?)
+            } else if let Some(code) = line.strip_prefix("!exit ") {
+                Step::Exit(code.trim().parse().map_err(|_| invalid("has an invalid exit code"))?)
+            } else {
+                return Err(invalid("is not a step"));
+            };
+            steps.push_back(step);
+        }
+        Ok(Script { steps })
+    }
+}
+
+impl Script {
+    /// Load a script from a file
+    pub fn load(path: &Path) -> AppResult<Self> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Script replaying a recorded transcript, the commands are expected in
+    /// the recorded order and answered with the recorded output, as fast as
+    /// GDB answered them
+    pub fn from_transcript(entries: &[TranscriptEntry]) -> Self {
+        let mut steps = VecDeque::new();
+        let mut last = None;
+        for entry in entries {
+            let untokenized = entry.line.trim_start_matches(|c: char| c.is_ascii_digit());
+            match entry.direction {
+                Direction::Command => steps.push_back(Step::Expect(untokenized.trim().to_string())),
+                // The fake writes its own prompts
+                Direction::Output if entry.line.trim_end() == "(gdb)" => continue,
+                Direction::Output => {
+                    let delay = last.map_or(0, |last| entry.timestamp.saturating_sub(last));
+                    if delay > 0 {
+                        steps.push_back(Step::Sleep(delay));
+                    }
+                    // Result records get the token of the replayed command
+                    let line = if untokenized.starts_with('^') { untokenized } else { &entry.line };
+                    steps.push_back(Step::Output(line.to_string()));
+                }
+            }
+            last = Some(entry.timestamp);
+        }
+        Script { steps }
+    }
+
+    /// Play back the script on the given pipes, returns the exit status
+    pub async fn serve<R, W, E>(self, stdin: R, stdout: W, stderr: E) -> i32
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+        E: AsyncWrite + Unpin,
+    {
+        let mut fake = Fake { steps: self.steps, stdout, stderr };
+        match fake.run(stdin).await {
+            Ok(code) => code,
+            Err(e) => {
+                debug!("Fake GDB pipe closed: {}", e);
+                1
+            }
+        }
+    }
+}
+
+struct Fake<W, E> {
+    steps: VecDeque<Step>,
+    stdout: W,
+    stderr: E,
+}
+
+impl<W: AsyncWrite + Unpin, E: AsyncWrite + Unpin> Fake<W, E> {
+    async fn run<R: AsyncRead + Unpin>(&mut self, stdin: R) -> std::io::Result<i32> {
+        if let Some(code) = self.play("").await? {
+            return Ok(code);
+        }
+        self.write("(gdb) ").await?;
+
+        let mut lines = BufReader::new(stdin).lines();
+        while let Some(line) = lines.next_line().await? {
+            let command = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            let token = &line[..line.len() - command.len()];
+            let command = command.trim();
+
+            let expected = match self.steps.front() {
+                Some(Step::Expect(expected)) => Some(expected.as_str()),
+                _ => None,
+            };
+            let matched = expected.is_some_and(|expected| match expected.strip_suffix('*') {
+                Some(prefix) => command.starts_with(prefix),
+                None => command == expected,
+            });
+
+            if matched {
+                self.steps.pop_front();
+                if let Some(code) = self.play(token).await? {
+                    return Ok(code);
+                }
+            } else if command.is_empty() {
+                self.write("^done").await?;
+            } else if command == "-gdb-exit" {
+                self.write(&format!("{}^exit", token)).await?;
+                return Ok(0);
+            } else {
+                let msg = format!(
+                    "fake-gdb: unexpected command {:?}, expected {:?}",
+                    command,
+                    expected.unwrap_or("end of script")
+                );
+                self.stderr.write_all(format!("{}\n", msg).as_bytes()).await?;
+                self.write(&format!("{}^error,msg={:?}", token, msg)).await?;
+            }
+            self.write("(gdb) ").await?;
+        }
+        Ok(0)
+    }
+
+    /// Play the steps up to the next expected command, result records get
+    /// `token`. Returns the exit status if the script exits.
+    async fn play(&mut self, token: &str) -> std::io::Result<Option<i32>> {
+        while let Some(step) = self.steps.front() {
+            match step.clone() {
+                Step::Expect(_) => break,
+                Step::Output(line) if line.starts_with('^') => {
+                    self.write(&format!("{}{}", token, line)).await?
+                }
+                Step::Output(line) => self.write(&line).await?,
+                Step::Stderr(line) => {
+                    self.stderr.write_all(format!("{}\n", line).as_bytes()).await?
+                }
+                Step::Sleep(millis) => tokio::time::sleep(Duration::from_millis(millis)).await,
+                Step::Exit(code) => return Ok(Some(code)),
+            }
+            self.steps.pop_front();
+        }
+        Ok(None)
+    }
+
+    async fn write(&mut self, line: &str) -> std::io::Result<()> {
+        self.stdout.write_all(format!("{}\n", line).as_bytes()).await?;
+        self.stdout.flush().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, duplex};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script: Script =
+            "# banner\n-> =thread-group-added,id=\"i1\"\n\n<- -exec-run\n-> ^running\n\
+            !sleep 10\n!stderr oops\n!exit 2\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            script.steps,
+            vec![
+                Step::Output("=thread-group-added,id=\"i1\"".to_string()),
+                Step::Expect("-exec-run".to_string()),
+                Step::Output("^running".to_string()),
+                Step::Sleep(10),
+                Step::Stderr("oops".to_string()),
+                Step::Exit(2),
+            ]
+        );
+        assert!(matches!("!sleep soon".parse::<Script>(), Err(AppError::InvalidArgument(_))));
+        assert!(matches!("^done".parse::<Script>(), Err(AppError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_from_transcript() {
+        let entry = |timestamp, direction, line: &str| TranscriptEntry {
+            timestamp,
+            direction,
+            line: line.to_string(),
+        };
+        let script = Script::from_transcript(&[
+            entry(100, Direction::Output, "=thread-group-added,id=\"i1\""),
+            entry(100, Direction::Output, "(gdb) "),
+            entry(110, Direction::Command, "3-exec-run"),
+            entry(110, Direction::Output, "3^running"),
+            entry(150, Direction::Output, "*stopped,reason=\"exited-normally\""),
+        ]);
+        assert_eq!(
+            script.steps,
+            vec![
+                Step::Output("=thread-group-added,id=\"i1\"".to_string()),
+                Step::Expect("-exec-run".to_string()),
+                Step::Output("^running".to_string()),
+                Step::Sleep(40),
+                Step::Output("*stopped,reason=\"exited-normally\"".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let script: Script =
+            "-> ~\"GNU gdb\"\n<- -break-insert main*\n-> ^done,bkpt={number=\"1\"}\n!exit 3\n"
+                .parse()
+                .unwrap();
+        let (mut stdin, fake_stdin) = duplex(1024);
+        let (fake_stdout, mut stdout) = duplex(1024);
+        let (fake_stderr, mut stderr) = duplex(1024);
+        stdin.write_all(b"\n1-exec-run\n2-break-insert main.c:5\n").await.unwrap();
+
+        let code = script.serve(fake_stdin, fake_stdout, fake_stderr).await;
+        assert_eq!(code, 3);
+
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).await.unwrap();
+        assert_eq!(
+            output,
+            "~\"GNU gdb\"\n(gdb) \n^done\n(gdb) \n\
+            1^error,msg=\"fake-gdb: unexpected command \\\"-exec-run\\\", expected \\\"-break-insert main*\\\"\"\n\
+            (gdb) \n2^done,bkpt={number=\"1\"}\n"
+        );
+        let mut errors = String::new();
+        stderr.read_to_string(&mut errors).await.unwrap();
+        assert!(errors.starts_with("fake-gdb: unexpected command"));
+    }
+}
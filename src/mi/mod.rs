@@ -1,27 +1,60 @@
 pub mod commands;
+pub mod dispatcher;
+pub mod fake;
 pub mod output;
+pub mod transcript;
 
+use std::collections::VecDeque;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{self, Arc};
+use std::time::Duration;
 
 use anyhow::Result;
+use dispatcher::Dispatcher;
 use output::process_output;
-use tokio::io::BufReader;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
-use tokio::sync::mpsc::{self, Sender};
-use tracing::debug;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{Mutex, Notify, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use transcript::{Direction, Recorder};
 
 use crate::error::{AppError, AppResult};
 
+/// Number of stderr lines of GDB kept for diagnostics
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Pipe the commands are written to
+type InputPipe = Box<dyn AsyncWrite + Send + Unpin>;
+/// Pipe the output and errors of GDB are read from
+type OutputPipe = Box<dyn AsyncRead + Send + Unpin>;
+
+/// What runs the MI interpreter
+enum Backend {
+    /// A GDB process
+    Process(Child),
+    /// The scripted fake, see [`fake`]
+    Fake(JoinHandle<i32>),
+}
+
 #[allow(clippy::upper_case_acronyms)]
 pub struct GDB {
-    pub process: Arc<Mutex<Child>>,
+    pid: Option<u32>,
+    stdin: Mutex<InputPipe>,
+    /// Records the commands and output, if enabled
+    transcript: Option<Arc<Recorder>>,
+    /// Asks the supervisor task to kill GDB
+    kill: Arc<Notify>,
+    /// How GDB exited, set by the supervisor task
+    exit_status: watch::Receiver<Option<String>>,
+    /// Last lines GDB wrote to stderr
+    stderr_tail: Arc<sync::Mutex<VecDeque<String>>>,
     is_running: Arc<AtomicBool>,
-    result_output: mpsc::Receiver<output::ResultRecord>,
+    dispatcher: Arc<Dispatcher>,
     current_command_token: AtomicU64,
     binary_path: PathBuf,
     init_options: Vec<OsString>,
@@ -65,6 +98,8 @@ pub struct GDBBuilder {
     pub opt_program: Option<PathBuf>,
     /// Use TTY for input/output by the program being debugged (--tty=TTY)
     pub opt_tty: Option<PathBuf>,
+    /// Record the MI transcript of the session to this file
+    pub transcript: Option<PathBuf>,
 }
 
 impl GDBBuilder {
@@ -84,10 +119,11 @@ impl GDBBuilder {
             opt_args: Vec::new(),
             opt_program: None,
             opt_tty: None,
+            transcript: None,
         }
     }
 
-    pub fn try_spawn(self, oob_sink: Sender<output::OutOfBandRecord>) -> AppResult<GDB> {
+    pub fn try_spawn(self, oob_sink: Sender<AppResult<output::OutOfBandRecord>>) -> AppResult<GDB> {
         let mut gdb_args = Vec::<OsString>::new();
         let mut init_options = Vec::<OsString>::new();
         if self.opt_nh {
@@ -149,42 +185,172 @@ impl GDBBuilder {
             gdb_args.push(program.into());
         }
 
-        let mut command = Command::new(self.gdb_path.clone());
-        command.arg("--interpreter=mi").args(gdb_args);
+        let gdb_path = self.gdb_path.to_str().unwrap_or_default();
+        let script = if let Some(script) = gdb_path.strip_prefix(fake::FAKE_GDB_PREFIX) {
+            debug!("Starting fake GDB with script {}", script);
+            Some(fake::Script::load(Path::new(script))?)
+        } else if let Some(transcript) = gdb_path.strip_prefix(transcript::REPLAY_PREFIX) {
+            debug!("Replaying transcript {}", transcript);
+            Some(fake::Script::from_transcript(&transcript::load(Path::new(transcript))?))
+        } else {
+            None
+        };
+        let recorder = self.transcript.as_deref().map(Recorder::create).transpose()?.map(Arc::new);
+
+        let (stdin, stdout, stderr, backend): (InputPipe, OutputPipe, OutputPipe, Backend) =
+            if let Some(script) = script {
+                let (stdin, fake_stdin) = tokio::io::duplex(64 * 1024);
+                let (fake_stdout, stdout) = tokio::io::duplex(64 * 1024);
+                let (fake_stderr, stderr) = tokio::io::duplex(64 * 1024);
+                let fake = tokio::spawn(script.serve(fake_stdin, fake_stdout, fake_stderr));
+                (Box::new(stdin), Box::new(stdout), Box::new(stderr), Backend::Fake(fake))
+            } else {
+                let mut command = Command::new(self.gdb_path.clone());
+                command.arg("--interpreter=mi").args(gdb_args);
 
-        debug!("Starting GDB process with command: {:?}", command);
+                debug!("Starting GDB process with command: {:?}", command);
 
-        let mut child = command
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| AppError::GDBError(format!("Failed to start GDB process: {}", e)))?;
+                let mut child = command
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| {
+                        AppError::GDBError(format!("Failed to start GDB process: {}", e))
+                    })?;
+                (
+                    Box::new(child.stdin.take().unwrap()),
+                    Box::new(child.stdout.take().unwrap()),
+                    Box::new(child.stderr.take().unwrap()),
+                    Backend::Process(child),
+                )
+            };
 
-        let stdout = BufReader::new(child.stdout.take().unwrap());
         let is_running = Arc::new(AtomicBool::new(false));
         let is_running_clone = is_running.clone();
-        let (result_input, result_output) = mpsc::channel(100);
-        tokio::spawn(process_output(stdout, result_input, oob_sink, is_running_clone));
+        let dispatcher = Arc::new(Dispatcher::default());
+        tokio::spawn(process_output(
+            stdout,
+            dispatcher.clone(),
+            oob_sink,
+            is_running_clone,
+            recorder.clone(),
+        ));
+
+        let stderr_tail = Arc::new(sync::Mutex::new(VecDeque::new()));
+        let stderr_done = tokio::spawn(collect_stderr(stderr, stderr_tail.clone()));
+
+        let pid = match &backend {
+            Backend::Process(child) => child.id(),
+            Backend::Fake(_) => None,
+        };
+        let kill = Arc::new(Notify::new());
+        let (exit_sender, exit_status) = watch::channel(None);
+        tokio::spawn(supervise(
+            backend,
+            kill.clone(),
+            stderr_done,
+            dispatcher.clone(),
+            is_running.clone(),
+            exit_sender,
+        ));
 
         let gdb = GDB {
-            process: Arc::new(Mutex::new(child)),
+            pid,
+            stdin: Mutex::new(stdin),
+            transcript: recorder,
+            kill,
+            exit_status,
+            stderr_tail,
             is_running,
+            dispatcher,
             current_command_token: AtomicU64::new(0),
             binary_path: self.gdb_path,
             init_options,
-            result_output,
         };
         Ok(gdb)
     }
 }
 
+/// Wait for GDB to exit, or kill it when asked to. Once it is gone the
+/// pending commands fail and the exit status is published.
+async fn supervise(
+    backend: Backend,
+    kill: Arc<Notify>,
+    stderr_done: JoinHandle<()>,
+    dispatcher: Arc<Dispatcher>,
+    is_running: Arc<AtomicBool>,
+    exit_status: watch::Sender<Option<String>>,
+) {
+    let status = match backend {
+        Backend::Process(mut child) => {
+            let status = tokio::select! {
+                status = child.wait() => status,
+                _ = kill.notified() => {
+                    if let Err(e) = child.start_kill() {
+                        debug!("Failed to kill GDB: {}", e);
+                    }
+                    child.wait().await
+                }
+            };
+            match status {
+                Ok(status) => status.to_string(),
+                Err(e) => format!("unknown, failed to wait for GDB: {}", e),
+            }
+        }
+        Backend::Fake(mut fake) => {
+            let code = tokio::select! {
+                code = &mut fake => code,
+                _ = kill.notified() => {
+                    fake.abort();
+                    fake.await
+                }
+            };
+            match code {
+                Ok(code) => format!("exit status: {}", code),
+                Err(_) => "killed".to_string(),
+            }
+        }
+    };
+    info!("GDB exited: {}", status);
+    // Let the last words of GDB reach the tail, the program may hold stderr
+    // open after GDB is gone though
+    let _ = tokio::time::timeout(Duration::from_millis(100), stderr_done).await;
+
+    dispatcher.close();
+    is_running.store(false, Ordering::SeqCst);
+    let _ = exit_status.send(Some(status));
+}
+
+/// Keep the last lines GDB writes to stderr
+async fn collect_stderr(stderr: impl AsyncRead + Unpin, tail: Arc<sync::Mutex<VecDeque<String>>>) {
+    let mut lines = BufReader::new(stderr).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                warn!("GDB stderr: {}", line);
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read GDB stderr: {}", e);
+                break;
+            }
+        }
+    }
+}
+
 impl GDB {
     #[cfg(unix)]
     pub async fn interrupt_execution(&self) -> Result<(), nix::Error> {
         use nix::sys::signal;
         use nix::unistd::Pid;
-        signal::kill(Pid::from_raw(self.process.lock().await.id().unwrap() as i32), signal::SIGINT)
+        let pid = self.pid.ok_or(nix::Error::ESRCH)?;
+        signal::kill(Pid::from_raw(pid as i32), signal::SIGINT)
     }
 
     #[cfg(windows)]
@@ -200,75 +366,101 @@ impl GDB {
         &self.init_options
     }
 
+    /// Kill GDB and wait until it is gone
+    pub async fn kill(&self) {
+        self.kill.notify_one();
+        self.wait_exit().await;
+    }
+
+    /// Wait until GDB exits and return its exit status
+    pub async fn wait_exit(&self) -> String {
+        let mut exit_status = self.exit_status.clone();
+        match exit_status.wait_for(|status| status.is_some()).await {
+            Ok(status) => status.clone().unwrap_or_default(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    /// Last lines GDB wrote to stderr, oldest first
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Number of timed out commands whose late result has not been drained yet
+    pub fn abandoned_commands(&self) -> usize {
+        self.dispatcher.abandoned_count()
+    }
+
     pub fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
 
-    pub fn new_token(&mut self) -> u64 {
+    pub fn new_token(&self) -> u64 {
         self.current_command_token.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// Write the command to GDB and wait for the result record carrying its
+    /// token. Several commands can be awaited concurrently, the reader task
+    /// routes each result to its issuer.
     pub async fn execute<C: std::borrow::Borrow<commands::MiCommand>>(
-        &mut self,
+        &self,
         command: C,
     ) -> AppResult<output::ResultRecord> {
         if self.is_running() {
             return Err(AppError::GDBBusy);
         }
 
+        let command = command.borrow();
         let command_token = self.new_token();
+        let (receiver, _guard) = if command.operation.is_empty() {
+            (self.dispatcher.register_untagged()?, None)
+        } else {
+            let receiver = self.dispatcher.register(command_token)?;
+            (receiver, Some(PendingGuard { dispatcher: &self.dispatcher, token: command_token }))
+        };
 
-        command
-            .borrow()
-            .write_interpreter_string(
-                &mut self
-                    .process
-                    .lock()
-                    .await
-                    .stdin
-                    .as_mut()
-                    .ok_or_else(|| AppError::GDBError("Failed to get stdin".to_string()))?,
-                command_token,
-            )
-            .await
-            .expect("write interpreter command");
-
-        match self.result_output.recv().await {
-            Some(record) => match record.token {
-                Some(token) => {
-                    if token == command_token {
-                        Ok(record)
-                    } else {
-                        Err(AppError::InvalidArgument(format!(
-                            "Unexpected command token: {}",
-                            token
-                        )))
-                    }
-                }
-                None if command.borrow().operation.is_empty() => Ok(record),
-                None => Err(AppError::GDBError(format!(
-                    "No command token, expecting {}",
-                    command_token
-                ))),
-            },
-            None => Err(AppError::GDBError("no result, expecting {}".to_string())),
+        self.write_command(command, command_token).await?;
+
+        receiver.await.map_err(|_| AppError::GDBQuit)
+    }
+
+    /// Write a command to GDB, recording it first so it precedes its output
+    /// in the transcript
+    async fn write_command(&self, command: &commands::MiCommand, token: u64) -> AppResult<()> {
+        let mut stdin = self.stdin.lock().await;
+        if let Some(transcript) = &self.transcript {
+            transcript
+                .record(Direction::Command, &command.interpreter_string(token).to_string_lossy());
         }
+        command.write_interpreter_string(&mut *stdin, token).await?;
+        Ok(())
     }
 
-    pub async fn execute_later<C: std::borrow::Borrow<commands::MiCommand>>(&mut self, command: C) {
+    /// Interrupt the running target. Unlike `execute` this is allowed while
+    /// the target is running, `-exec-interrupt` is written out of band and
+    /// its result awaited, the stop itself is reported by a `*stopped`
+    /// record.
+    pub async fn interrupt(&self) -> AppResult<()> {
         let command_token = self.new_token();
-        command
-            .borrow()
-            .write_interpreter_string(
-                &mut self.process.lock().await.stdin.as_mut().unwrap(),
-                command_token,
-            )
-            .await
-            .expect("write interpreter command");
-        let _ = self.result_output.recv().await;
+        let receiver = self.dispatcher.register(command_token)?;
+        let _guard = PendingGuard { dispatcher: &self.dispatcher, token: command_token };
+
+        self.write_command(&commands::MiCommand::exec_interrupt(), command_token).await?;
+
+        let record = receiver.await.map_err(|_| AppError::GDBQuit)?;
+        if record.class == output::ResultClass::Error {
+            return Err(AppError::GDBError(record.results.to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn execute_later<C: std::borrow::Borrow<commands::MiCommand>>(&self, command: C) {
+        if let Err(e) = self.execute(command).await {
+            debug!("Ignoring result of the command: {}", e);
+        }
     }
 
-    pub async fn is_session_active(&mut self) -> AppResult<bool> {
+    pub async fn is_session_active(&self) -> AppResult<bool> {
         let res = self.execute(commands::MiCommand::thread_info(None)).await?;
         if let Some(threads) = res.results.get("threads") {
             if let Some(threads) = threads.as_array() {
@@ -281,3 +473,16 @@ impl GDB {
         }
     }
 }
+
+/// Abandons the waiter of a command if the command is dropped before its
+/// result arrives, e.g. on timeout
+struct PendingGuard<'a> {
+    dispatcher: &'a Dispatcher,
+    token: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.dispatcher.abandon(self.token);
+    }
+}
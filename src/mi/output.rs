@@ -1,8 +1,8 @@
 // use std::io::{BufRead, BufReader, Read};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::{fmt, io};
 
-use anyhow::Result;
 use nom::branch::alt;
 use nom::bytes::complete::{is_not, tag, take_while_m_n};
 use nom::character::complete::{char, digit1, line_ending, multispace1};
@@ -13,7 +13,7 @@ use nom::sequence::{delimited, preceded, separated_pair};
 use nom::{IResult, Parser};
 use serde_json::{Map, Value};
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResultClass {
@@ -34,20 +34,43 @@ pub enum BreakPointEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThreadEvent {
     Created,
+    GroupAdded,
+    GroupRemoved,
     GroupStarted,
     Exited,
     GroupExited,
     Selected,
 }
 
+/// Changes of trace state variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsvEvent {
+    Created,
+    Deleted,
+    Modified,
+}
+
+/// Start and stop of process record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordEvent {
+    Started,
+    Stopped,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AsyncClass {
     Running,
     Stopped,
     CmdParamChanged,
     LibraryLoaded,
+    LibraryUnloaded,
+    MemoryChanged,
+    RegisterChanged,
+    TraceframeChanged,
     Thread(ThreadEvent),
     BreakPoint(BreakPointEvent),
+    Tsv(TsvEvent),
+    Record(RecordEvent),
     Other(String), //?
 }
 
@@ -65,6 +88,57 @@ pub enum StreamKind {
     Log,
 }
 
+impl fmt::Display for AsyncClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsyncClass::Running => write!(f, "running"),
+            AsyncClass::Stopped => write!(f, "stopped"),
+            AsyncClass::CmdParamChanged => write!(f, "cmd-param-changed"),
+            AsyncClass::LibraryLoaded => write!(f, "library-loaded"),
+            AsyncClass::LibraryUnloaded => write!(f, "library-unloaded"),
+            AsyncClass::MemoryChanged => write!(f, "memory-changed"),
+            AsyncClass::RegisterChanged => write!(f, "register-changed"),
+            AsyncClass::TraceframeChanged => write!(f, "traceframe-changed"),
+            AsyncClass::Thread(ThreadEvent::Created) => write!(f, "thread-created"),
+            AsyncClass::Thread(ThreadEvent::GroupAdded) => write!(f, "thread-group-added"),
+            AsyncClass::Thread(ThreadEvent::GroupRemoved) => write!(f, "thread-group-removed"),
+            AsyncClass::Thread(ThreadEvent::GroupStarted) => write!(f, "thread-group-started"),
+            AsyncClass::Thread(ThreadEvent::Exited) => write!(f, "thread-exited"),
+            AsyncClass::Thread(ThreadEvent::GroupExited) => write!(f, "thread-group-exited"),
+            AsyncClass::Thread(ThreadEvent::Selected) => write!(f, "thread-selected"),
+            AsyncClass::BreakPoint(BreakPointEvent::Created) => write!(f, "breakpoint-created"),
+            AsyncClass::BreakPoint(BreakPointEvent::Deleted) => write!(f, "breakpoint-deleted"),
+            AsyncClass::BreakPoint(BreakPointEvent::Modified) => write!(f, "breakpoint-modified"),
+            AsyncClass::Tsv(TsvEvent::Created) => write!(f, "tsv-created"),
+            AsyncClass::Tsv(TsvEvent::Deleted) => write!(f, "tsv-deleted"),
+            AsyncClass::Tsv(TsvEvent::Modified) => write!(f, "tsv-modified"),
+            AsyncClass::Record(RecordEvent::Started) => write!(f, "record-started"),
+            AsyncClass::Record(RecordEvent::Stopped) => write!(f, "record-stopped"),
+            AsyncClass::Other(class) => write!(f, "{}", class),
+        }
+    }
+}
+
+impl fmt::Display for AsyncKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsyncKind::Exec => write!(f, "exec"),
+            AsyncKind::Status => write!(f, "status"),
+            AsyncKind::Notify => write!(f, "notify"),
+        }
+    }
+}
+
+impl fmt::Display for StreamKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamKind::Console => write!(f, "console"),
+            StreamKind::Target => write!(f, "target"),
+            StreamKind::Log => write!(f, "log"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResultRecord {
     pub(crate) token: Option<u64>,
@@ -90,27 +164,74 @@ enum Output {
 
 use tokio::sync::mpsc::Sender;
 
+use crate::error::{AppError, AppResult};
+use crate::mi::dispatcher::Dispatcher;
+use crate::mi::transcript::{Direction, Recorder};
+
+/// Forward an out of band record, or an error of the reader, to the session
+async fn send_out_of_band(
+    pipe: &Sender<AppResult<OutOfBandRecord>>,
+    record: AppResult<OutOfBandRecord>,
+) {
+    // The session may be gone already, keep reading to serve the results
+    if let Err(e) = pipe.send(record).await {
+        debug!("Out of band pipe closed, discarding {:?}", e.0);
+    }
+}
+
+/// Read errors which leave the stream usable, the read is simply retried
+fn is_recoverable(kind: io::ErrorKind) -> bool {
+    matches!(kind, io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+/// Read the output of GDB line by line until it closes, results go to the
+/// dispatcher, everything else to `out_of_band_pipe`. Lines that can't be
+/// parsed and read errors are reported on the pipe too, they don't stop the
+/// reader unless the stream is broken.
 pub async fn process_output<T: AsyncRead + Unpin>(
     output: T,
-    result_pipe: Sender<ResultRecord>,
-    out_of_band_pipe: Sender<OutOfBandRecord>,
+    dispatcher: Arc<Dispatcher>,
+    out_of_band_pipe: Sender<AppResult<OutOfBandRecord>>,
     is_running: Arc<AtomicBool>,
+    transcript: Option<Arc<Recorder>>,
 ) {
     let mut reader = BufReader::new(output);
+    // Kept across read errors, the rest of the line comes with the next read
+    let mut bytes = Vec::new();
 
     loop {
-        let mut buffer = String::new();
-        match reader.read_line(&mut buffer).await {
-            Ok(0) => {
+        match reader.read_until(b'\n', &mut bytes).await {
+            Ok(0) if bytes.is_empty() => {
+                dispatcher.close();
                 return;
             }
             Ok(_) => {
+                let bytes = std::mem::take(&mut bytes);
+                if let Some(transcript) = &transcript {
+                    let line = String::from_utf8_lossy(&bytes);
+                    transcript.record(Direction::Output, line.trim_end_matches(['\r', '\n']));
+                }
+                let buffer = match String::from_utf8(bytes) {
+                    Ok(buffer) => buffer,
+                    Err(e) => {
+                        let line = String::from_utf8_lossy(e.as_bytes()).trim_end().to_string();
+                        warn!("Invalid UTF-8 in GDB output: {}", line);
+                        let reason = e.utf8_error().to_string();
+                        send_out_of_band(
+                            &out_of_band_pipe,
+                            Err(AppError::ParseError { line, reason }),
+                        )
+                        .await;
+                        continue;
+                    }
+                };
                 info!("{}", buffer.trim_end());
 
                 let parse_result = match Output::parse(&buffer) {
                     Ok(r) => r,
                     Err(e) => {
-                        error!("PARSING ERROR: {}", e);
+                        warn!("{}", e);
+                        send_out_of_band(&out_of_band_pipe, Err(e)).await;
                         continue;
                     }
                 };
@@ -124,7 +245,7 @@ pub async fn process_output<T: AsyncRead + Unpin>(
                             ResultClass::Error => is_running.store(false, Ordering::SeqCst),
                             _ => {}
                         }
-                        result_pipe.send(record).await.expect("send result to pipe");
+                        dispatcher.dispatch(record);
                     }
                     Output::OutOfBand(record) => {
                         if let OutOfBandRecord::AsyncRecord { class: AsyncClass::Stopped, .. } =
@@ -132,40 +253,46 @@ pub async fn process_output<T: AsyncRead + Unpin>(
                         {
                             is_running.store(false, Ordering::SeqCst);
                         }
-                        out_of_band_pipe
-                            .send(record)
-                            .await
-                            .expect("send out of band record to pipe");
+                        send_out_of_band(&out_of_band_pipe, Ok(record)).await;
                     }
                     Output::GDBLine => {}
                     //Output::SomethingElse(_) => { /*println!("SOMETHING ELSE: {}", str);*/ }
                     Output::SomethingElse(text) => {
-                        out_of_band_pipe
-                            .send(OutOfBandRecord::StreamRecord {
-                                kind: StreamKind::Target,
-                                data: text,
-                            })
-                            .await
-                            .expect("send out of band record to pipe");
+                        let record =
+                            OutOfBandRecord::StreamRecord { kind: StreamKind::Target, data: text };
+                        send_out_of_band(&out_of_band_pipe, Ok(record)).await;
                     }
                 }
             }
+            Err(e) if is_recoverable(e.kind()) => {
+                warn!("Recoverable error reading GDB output: {}", e);
+                // The bytes read so far stay in the buffer, the next read
+                // continues the same line
+                send_out_of_band(&out_of_band_pipe, Err(e.into())).await;
+            }
             Err(e) => {
-                panic!("{}", e);
+                error!("Failed to read GDB output: {}", e);
+                send_out_of_band(&out_of_band_pipe, Err(e.into())).await;
+                dispatcher.close();
+                return;
             }
         }
     }
 }
 
 impl Output {
-    fn parse(line: &str) -> Result<Self, String> {
+    fn parse(line: &str) -> AppResult<Self> {
         match output(line) {
             Ok((_, c)) => Ok(c),
-            Err(e) => match e {
-                nom::Err::Incomplete(e) => Err(format!("parsing line: incomplete {:?}", e)),
-                nom::Err::Error(e) => Err(format!("parse error: {}", e)),
-                nom::Err::Failure(e) => Err(format!("parse failure: {}", e)),
-            },
+            Err(e) => Err(AppError::ParseError {
+                line: line.trim_end().to_string(),
+                reason: match e {
+                    nom::Err::Incomplete(e) => format!("incomplete {:?}", e),
+                    nom::Err::Error(e) | nom::Err::Failure(e) => {
+                        format!("{:?} at {:?}", e.code, e.input.trim_end())
+                    }
+                },
+            }),
         }
     }
 }
@@ -267,6 +394,22 @@ fn string(input: &str) -> IResult<&str, String> {
     delimited(char('"'), build_string, char('"')).parse(input)
 }
 
+/// The quoted strings of a value printed by GDB, e.g. the two of the slice
+/// `&[&str](size=2) = {"a", "b"}`
+pub fn quoted_strings(mut input: &str) -> Vec<String> {
+    let mut strings = vec![];
+    while let Some(start) = input.find('"') {
+        match string(&input[start..]) {
+            Ok((rest, s)) => {
+                strings.push(s);
+                input = rest;
+            }
+            Err(_) => input = &input[start + 1..],
+        }
+    }
+    strings
+}
+
 fn to_map(v: Vec<(String, Value)>) -> Map<String, Value> {
     Map::from_iter(v.into_iter())
 }
@@ -283,6 +426,11 @@ fn json_value(input: &str) -> IResult<&str, Value> {
         map(delimited(char('{'), separated_list0(char(','), key_value), char('}')), |results| {
             Value::Object(to_map(results))
         }),
+        // Against its spec, gdb(mi) emits the commands of breakpoints as a
+        // tuple of bare values, e.g. script={"silent","print x"}
+        map(delimited(char('{'), separated_list0(char(','), json_value), char('}')), |values| {
+            Value::Array(values)
+        }),
         map(delimited(char('['), separated_list0(char(','), json_value), char(']')), |values| {
             Value::Array(values)
         }),
@@ -348,16 +496,33 @@ fn async_class(input: &str) -> IResult<&str, AsyncClass> {
     alt((
         value(AsyncClass::Running, tag("running")),
         value(AsyncClass::Stopped, tag("stopped")),
-        value(AsyncClass::Thread(ThreadEvent::Created), tag("thread-created")),
-        value(AsyncClass::Thread(ThreadEvent::GroupStarted), tag("thread-group-started")),
-        value(AsyncClass::Thread(ThreadEvent::Exited), tag("thread-exited")),
-        value(AsyncClass::Thread(ThreadEvent::GroupExited), tag("thread-group-exited")),
-        value(AsyncClass::Thread(ThreadEvent::Selected), tag("thread-selected")),
+        alt((
+            value(AsyncClass::Thread(ThreadEvent::Created), tag("thread-created")),
+            value(AsyncClass::Thread(ThreadEvent::GroupAdded), tag("thread-group-added")),
+            value(AsyncClass::Thread(ThreadEvent::GroupRemoved), tag("thread-group-removed")),
+            value(AsyncClass::Thread(ThreadEvent::GroupStarted), tag("thread-group-started")),
+            value(AsyncClass::Thread(ThreadEvent::Exited), tag("thread-exited")),
+            value(AsyncClass::Thread(ThreadEvent::GroupExited), tag("thread-group-exited")),
+            value(AsyncClass::Thread(ThreadEvent::Selected), tag("thread-selected")),
+        )),
         value(AsyncClass::CmdParamChanged, tag("cmd-param-changed")),
         value(AsyncClass::LibraryLoaded, tag("library-loaded")),
-        value(AsyncClass::BreakPoint(BreakPointEvent::Created), tag("breakpoint-created")),
-        value(AsyncClass::BreakPoint(BreakPointEvent::Deleted), tag("breakpoint-deleted")),
-        value(AsyncClass::BreakPoint(BreakPointEvent::Modified), tag("breakpoint-modified")),
+        value(AsyncClass::LibraryUnloaded, tag("library-unloaded")),
+        value(AsyncClass::MemoryChanged, tag("memory-changed")),
+        value(AsyncClass::RegisterChanged, tag("register-changed")),
+        value(AsyncClass::TraceframeChanged, tag("traceframe-changed")),
+        alt((
+            value(AsyncClass::BreakPoint(BreakPointEvent::Created), tag("breakpoint-created")),
+            value(AsyncClass::BreakPoint(BreakPointEvent::Deleted), tag("breakpoint-deleted")),
+            value(AsyncClass::BreakPoint(BreakPointEvent::Modified), tag("breakpoint-modified")),
+        )),
+        alt((
+            value(AsyncClass::Tsv(TsvEvent::Created), tag("tsv-created")),
+            value(AsyncClass::Tsv(TsvEvent::Deleted), tag("tsv-deleted")),
+            value(AsyncClass::Tsv(TsvEvent::Modified), tag("tsv-modified")),
+        )),
+        value(AsyncClass::Record(RecordEvent::Started), tag("record-started")),
+        value(AsyncClass::Record(RecordEvent::Stopped), tag("record-stopped")),
         map(is_not(","), |msg: &str| AsyncClass::Other(msg.to_string())),
     ))
     .parse(input)
@@ -366,9 +531,12 @@ fn async_class(input: &str) -> IResult<&str, AsyncClass> {
 /// \[token\] async-kind async-class ( "," result )* nl,
 /// where async-kind is one of: * (exec), + (status), = (notify),
 /// and async-class is one of: running, stopped, thread-created,
-/// thread-group-started, thread-exited, thread-group-exited, thread-selected,
-/// cmd-param-changed, library-loaded, breakpoint-created, breakpoint-deleted,
-/// breakpoint-modified, other and result is a json object
+/// thread-group-added, thread-group-removed, thread-group-started,
+/// thread-exited, thread-group-exited, thread-selected, cmd-param-changed,
+/// library-loaded, library-unloaded, memory-changed, register-changed,
+/// traceframe-changed, breakpoint-created, breakpoint-deleted,
+/// breakpoint-modified, tsv-created, tsv-deleted, tsv-modified,
+/// record-started, record-stopped, other and result is a json object
 fn async_record(input: &str) -> IResult<&str, OutOfBandRecord> {
     map(
         (opt(token), async_kind, async_class, many0(preceded(char(','), key_value))),
@@ -407,8 +575,19 @@ fn prompt(input: &str) -> IResult<&str, Output> {
     value(Output::GDBLine, tag("(gdb) ")).parse(input)
 }
 
+/// Any other line, e.g. output of the program when it shares the terminal
+/// of GDB. Lines that look like a record but didn't parse as one are
+/// rejected, so they are reported instead of passing as program output.
 fn debug_line(input: &str) -> IResult<&str, Output> {
-    Ok(("", Output::SomethingElse(input.to_string())))
+    let end = input.find(['\r', '\n']).unwrap_or(input.len());
+    let line = &input[..end];
+    let record = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if record.starts_with(['^', '*', '+', '=', '~', '@', '&']) {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    let output =
+        if line.is_empty() { Output::GDBLine } else { Output::SomethingElse(line.to_string()) };
+    Ok((&input[end..], output))
 }
 
 fn output(input: &str) -> IResult<&str, Output> {
@@ -568,4 +747,123 @@ mod test {
             panic!("output is not a result record");
         }
     }
+
+    #[test]
+    fn test_breakpoint_script() {
+        let output = Output::parse(
+            "^done,bkpt={number=\"4\",type=\"dprintf\",disp=\"keep\",enabled=\"y\",\
+            script={\"printf \\\"x=%d\\\\n\\\", x\",\"silent\"}}\n",
+        )
+        .unwrap();
+        let Output::Result(result) = output else { panic!("output is not a result record") };
+        assert_eq!(
+            result.results["bkpt"]["script"],
+            serde_json::json!(["printf \"x=%d\\n\", x", "silent"])
+        );
+    }
+
+    #[test]
+    fn test_quoted_strings() {
+        assert_eq!(
+            quoted_strings("&[&str](size=2) = {\"index \\\"out\\\" of \", \"\"}"),
+            vec!["index \"out\" of ".to_string(), String::new()]
+        );
+        assert_eq!(quoted_strings("\"src/main.rs\""), vec!["src/main.rs".to_string()]);
+        assert!(quoted_strings("42").is_empty());
+    }
+
+    #[test]
+    fn test_async_classes() {
+        for (line, expected) in [
+            ("=thread-group-added,id=\"i1\"\n", AsyncClass::Thread(ThreadEvent::GroupAdded)),
+            ("=library-unloaded,id=\"/lib/libc.so.6\"\n", AsyncClass::LibraryUnloaded),
+            (
+                "=memory-changed,thread-group=\"i1\",addr=\"0x601040\",len=\"0x4\"\n",
+                AsyncClass::MemoryChanged,
+            ),
+            ("=tsv-modified,name=\"n\",initial=\"1\"\n", AsyncClass::Tsv(TsvEvent::Modified)),
+            (
+                "=record-started,thread-group=\"i1\",method=\"full\"\n",
+                AsyncClass::Record(RecordEvent::Started),
+            ),
+            ("=new-ui-event,id=\"1\"\n", AsyncClass::Other("new-ui-event".to_string())),
+        ] {
+            match Output::parse(line) {
+                Ok(Output::OutOfBand(OutOfBandRecord::AsyncRecord { class, .. })) => {
+                    assert_eq!(class, expected);
+                    assert!(line[1..].starts_with(&format!("{},", class)));
+                }
+                other => panic!("{} is not an async record: {:?}", line, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_output_reports_bad_lines() {
+        let input: &[u8] =
+            b"^done,bkpt={number=\n\xff\nhello\n*stopped,reason=\"end-stepping-range\"\n^done\n";
+        let dispatcher = Arc::new(Dispatcher::default());
+        let result = dispatcher.register_untagged().unwrap();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        process_output(input, dispatcher, sender, Arc::new(AtomicBool::new(false)), None).await;
+
+        match receiver.recv().await {
+            Some(Err(AppError::ParseError { line, .. })) => assert_eq!(line, "^done,bkpt={number="),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(matches!(receiver.recv().await, Some(Err(AppError::ParseError { .. }))));
+        match receiver.recv().await {
+            Some(Ok(OutOfBandRecord::StreamRecord { kind: StreamKind::Target, data })) => {
+                assert_eq!(data, "hello")
+            }
+            other => panic!("expected program output, got {:?}", other),
+        }
+        assert!(matches!(
+            receiver.recv().await,
+            Some(Ok(OutOfBandRecord::AsyncRecord { class: AsyncClass::Stopped, .. }))
+        ));
+        assert_eq!(result.await.unwrap().class, ResultClass::Done);
+    }
+
+    /// Returns the chunks one per read, a `None` chunk fails the read
+    struct ChunkedReader(std::collections::VecDeque<Option<&'static [u8]>>);
+
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<io::Result<()>> {
+            std::task::Poll::Ready(match self.0.pop_front() {
+                Some(Some(chunk)) => {
+                    buf.put_slice(chunk);
+                    Ok(())
+                }
+                Some(None) => Err(io::ErrorKind::Interrupted.into()),
+                None => Ok(()),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_output_resumes_line_after_read_error() {
+        let input = ChunkedReader(
+            [Some(&b"*stopped,reason=\"end-"[..]), None, Some(b"stepping-range\"\n^done\n")].into(),
+        );
+        let dispatcher = Arc::new(Dispatcher::default());
+        let result = dispatcher.register_untagged().unwrap();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        process_output(input, dispatcher, sender, Arc::new(AtomicBool::new(false)), None).await;
+
+        assert!(matches!(receiver.recv().await, Some(Err(AppError::IoError(_)))));
+        match receiver.recv().await {
+            Some(Ok(OutOfBandRecord::AsyncRecord {
+                class: AsyncClass::Stopped, results, ..
+            })) => {
+                assert_eq!(results["reason"], "end-stepping-range")
+            }
+            other => panic!("expected the whole stop, got {:?}", other),
+        }
+        assert_eq!(result.await.unwrap().class, ResultClass::Done);
+    }
 }
@@ -0,0 +1,108 @@
+//! Raw MI transcripts of sessions, for bug reports and parser regression
+//! fixtures.
+//!
+//! A transcript is a JSONL file with one [`TranscriptEntry`] per line, every
+//! command written to GDB and every line read from it. A GDB path of the form
+//! `replay:<transcript>` plays a transcript back through the fake GDB, so a
+//! recorded session can be reproduced without a debugger.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::error::AppResult;
+
+/// Prefix of the GDB path replaying a transcript, followed by its path
+pub const REPLAY_PREFIX: &str = "replay:";
+
+/// Which way a line went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// A command written to GDB
+    Command,
+    /// A line of output read from GDB
+    Output,
+}
+
+/// A line of a transcript
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Time in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    pub direction: Direction,
+    /// The line without its line ending
+    pub line: String,
+}
+
+/// Appends the lines of a session to its transcript file
+pub struct Recorder {
+    file: Mutex<LineWriter<File>>,
+}
+
+impl Recorder {
+    /// Start a transcript at `path`, truncating an existing one
+    pub fn create(path: &Path) -> AppResult<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(Self { file: Mutex::new(LineWriter::new(File::create(path)?)) })
+    }
+
+    /// Append a line, failures are logged but don't affect the session
+    pub fn record(&self, direction: Direction, line: &str) {
+        let entry = TranscriptEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            direction,
+            line: line.to_string(),
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|json| writeln!(self.file.lock().unwrap(), "{}", json));
+        if let Err(e) = result {
+            error!("Failed to record transcript: {}", e);
+        }
+    }
+}
+
+/// Read a transcript
+pub fn load(path: &Path) -> AppResult<Vec<TranscriptEntry>> {
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record_and_load() {
+        let path = std::env::temp_dir().join(format!("transcript-{}.jsonl", uuid::Uuid::new_v4()));
+        let recorder = Recorder::create(&path).unwrap();
+        recorder.record(Direction::Command, "1-exec-run");
+        recorder.record(Direction::Output, "1^running");
+        drop(recorder);
+
+        let entries = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].direction, Direction::Command);
+        assert_eq!(entries[0].line, "1-exec-run");
+        assert_eq!(entries[1].direction, Direction::Output);
+        assert!(entries[1].timestamp >= entries[0].timestamp);
+    }
+}
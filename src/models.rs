@@ -4,6 +4,7 @@ use std::fmt::Display;
 use std::ops::{Add, Sub};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_until};
@@ -17,7 +18,8 @@ use serde_with::{DisplayFromStr, serde_as, skip_serializing_none};
 use tracing::debug;
 
 use crate::error::AppError;
-use crate::mi::commands::BreakPointNumber;
+use crate::mi::commands::{BreakPointNumber, CatchEvent, WatchMode};
+use crate::mi::output::{AsyncClass, OutOfBandRecord};
 
 /// GDB session information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +30,17 @@ pub struct GDBSession {
     pub status: GDBSessionStatus,
     /// Creation time
     pub created_at: u64,
+    /// Set when a command timed out and GDB did not answer the follow-up
+    /// probe either, results of this session may be unreliable
+    pub desynced: bool,
+    /// How the GDB process exited, once the session is terminated
+    pub exit_status: Option<String>,
+    /// Last lines GDB wrote to stderr when it exited
+    pub stderr_tail: Vec<String>,
+    /// Number of output lines of GDB that could not be parsed
+    pub parse_failures: u64,
+    /// File the MI transcript of the session is recorded to
+    pub transcript: Option<PathBuf>,
 }
 
 /// GDB session status
@@ -39,6 +52,8 @@ pub enum GDBSessionStatus {
     Running,
     /// Program stopped at breakpoint
     Stopped,
+    /// Program exited, it can be started again
+    Exited,
     /// Session terminated
     Terminated,
 }
@@ -140,20 +155,93 @@ impl<'de> Deserialize<'de> for Enabled {
     }
 }
 
+#[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BreakPoint {
     pub number: BreakPointNumber,
-    #[serde(rename = "addr")]
+    #[serde(rename = "addr", default, deserialize_with = "breakpoint_address")]
     pub address: Option<Address64>,
     pub enabled: Enabled,
+    #[serde(rename = "func")]
+    pub function: Option<String>,
     #[serde(flatten)]
     pub src_pos: Option<SrcPosition>, // not present if debug information is missing!
     pub r#type: String,
     #[serde(rename = "disp")]
     pub display: String,
+    /// Only stops if this expression is true
+    #[serde(rename = "cond")]
+    pub condition: Option<String>,
+    /// Number of times the breakpoint was hit
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub times: Option<usize>,
+    /// Number of hits still ignored
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, rename = "ignore")]
+    pub ignore_count: Option<usize>,
+    /// Only stops in this thread
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub thread: Option<u32>,
+    /// Location as it was given when setting the breakpoint
+    #[serde(rename = "original-location")]
+    pub original_location: Option<String>,
+    /// Expression watched by a watchpoint, what a catchpoint catches
+    pub what: Option<String>,
+    /// Event caught by a catchpoint
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, rename = "catch-type")]
+    pub catch_type: Option<CatchEvent>,
+    /// Location of a pending breakpoint, which isn't resolved yet
+    pub pending: Option<String>,
+    /// Commands run when the breakpoint is hit, e.g. the printf of a dprintf
+    pub script: Option<Vec<String>>,
+    /// Locations of a breakpoint resolved to several addresses, e.g. an
+    /// inlined function
+    pub locations: Option<Vec<ResolvedLocation>>,
 }
 
+impl BreakPoint {
+    /// What a watchpoint watches for, none for other kinds of breakpoints
+    pub fn watch_mode(&self) -> Option<WatchMode> {
+        match self.r#type.as_str() {
+            "watchpoint" | "hw watchpoint" => Some(WatchMode::Write),
+            "read watchpoint" => Some(WatchMode::Read),
+            "acc watchpoint" => Some(WatchMode::Access),
+            _ => None,
+        }
+    }
+}
+
+/// One of several locations a breakpoint resolved to, numbered `major.minor`
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedLocation {
+    pub number: BreakPointNumber,
+    #[serde(rename = "addr", default, deserialize_with = "breakpoint_address")]
+    pub address: Option<Address64>,
+    pub enabled: Enabled,
+    #[serde(rename = "func")]
+    pub function: Option<String>,
+    #[serde(flatten)]
+    pub src_pos: Option<SrcPosition>,
+}
+
+/// Address of a breakpoint, none for the `<MULTIPLE>` and `<PENDING>`
+/// placeholders and for watchpoints
+fn breakpoint_address<'de, D>(deserializer: D) -> Result<Option<Address64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let addr: Option<String> = Deserialize::deserialize(deserializer)?;
+    Ok(addr.filter(|addr| !addr.is_empty() && !addr.starts_with('<')).map(Address64::from))
+}
+
+/// Breakpoints of a session, kept up to date from the breakpoint commands and
+/// the `=breakpoint-*` notifications
+#[derive(Debug)]
 pub struct BreakPointSet {
     map: HashMap<BreakPointNumber, BreakPoint>,
     pub last_change: std::time::Instant,
@@ -183,6 +271,176 @@ impl BreakPointSet {
         }
         self.notify_change();
     }
+
+    pub fn get(&self, bp_num: BreakPointNumber) -> Option<&BreakPoint> {
+        self.map.get(&bp_num)
+    }
+
+    /// Replace all breakpoints, e.g. with the ones listed by GDB
+    pub fn replace(&mut self, breakpoints: Vec<BreakPoint>) {
+        self.map = breakpoints.into_iter().map(|bp| (bp.number, bp)).collect();
+        self.notify_change();
+    }
+
+    /// Specs of the breakpoints, in the order they were set. Logpoints are
+    /// left out, their output is tied to the session that set them, and so
+    /// are catchpoints, which have no location.
+    pub fn export(&self) -> Vec<BreakPointSpec> {
+        let mut breakpoints: Vec<_> = self
+            .map
+            .values()
+            .filter(|bp| bp.r#type != "dprintf" && bp.catch_type.is_none())
+            .collect();
+        breakpoints.sort_by_key(|bp| (bp.number.major, bp.number.minor));
+        breakpoints.into_iter().map(BreakPointSpec::from).collect()
+    }
+}
+
+/// A breakpoint or watchpoint as exported, everything needed to set it again
+/// in another session. The location is the one it was set at rather than the
+/// resolved address, so it survives rebuilding the program.
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakPointSpec {
+    /// Location in the syntax of GDB, e.g. main.c:5, main or *0x401136, or
+    /// the watched expression of a watchpoint
+    pub location: String,
+    /// Set for watchpoints
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub watch: Option<WatchMode>,
+    pub condition: Option<String>,
+    pub ignore_count: Option<usize>,
+    pub thread: Option<u32>,
+    #[serde(default)]
+    pub temporary: bool,
+    #[serde(default)]
+    pub hardware: bool,
+    #[serde(default)]
+    pub pending: bool,
+    pub enabled: bool,
+}
+
+impl From<&BreakPoint> for BreakPointSpec {
+    fn from(bp: &BreakPoint) -> Self {
+        let watch = bp.watch_mode();
+        let location = if watch.is_some() {
+            bp.what.clone()
+        } else {
+            bp.original_location.clone().or_else(|| bp.pending.clone())
+        };
+        let location = location
+            .or_else(|| {
+                bp.src_pos.as_ref().map(|pos| format!("{}:{}", pos.fullname.display(), pos.line))
+            })
+            .or_else(|| bp.address.map(|addr| format!("*{}", String::from(addr))))
+            .unwrap_or_default();
+        BreakPointSpec {
+            location,
+            watch,
+            condition: bp.condition.clone(),
+            ignore_count: bp.ignore_count,
+            thread: bp.thread,
+            temporary: bp.display == "del",
+            hardware: bp.r#type == "hw breakpoint",
+            pending: bp.pending.is_some(),
+            enabled: bp.enabled.0,
+        }
+    }
+}
+
+/// Actions run by the server when a breakpoint is hit, before the stop is
+/// reported
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakPointActions {
+    /// Expressions to evaluate
+    #[serde(default)]
+    pub expressions: Vec<String>,
+    /// Numbers of the registers to capture
+    #[serde(default)]
+    pub registers: Vec<String>,
+    /// Number of frames of the backtrace to capture, none if 0
+    #[serde(default)]
+    pub backtrace_depth: usize,
+    /// Resume the target after collecting instead of reporting the stop
+    #[serde(default)]
+    pub auto_continue: bool,
+}
+
+/// Value of an expression collected at a breakpoint hit
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectedValue {
+    pub expression: String,
+    pub value: Option<String>,
+    /// Why the expression could not be evaluated
+    pub error: Option<String>,
+}
+
+/// What the actions of a breakpoint collected at one hit
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakPointSnapshot {
+    /// Number of the hit among the collected ones of the breakpoint, from 1
+    pub hit: u64,
+    /// Time of the hit in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    /// Thread that hit the breakpoint
+    pub thread_id: Option<u32>,
+    /// Frame the breakpoint was hit in
+    pub frame: Option<StackFrame>,
+    pub values: Vec<CollectedValue>,
+    pub registers: Option<Vec<Register>>,
+    pub backtrace: Option<Vec<StackFrame>>,
+    /// Errors collecting the registers or the backtrace
+    pub errors: Option<Vec<String>>,
+}
+
+/// The last snapshots of every breakpoint of a session
+#[derive(Debug, Default)]
+pub struct SnapshotStore {
+    snapshots: HashMap<usize, VecDeque<BreakPointSnapshot>>,
+    hits: HashMap<usize, u64>,
+    capacity: usize,
+}
+
+impl SnapshotStore {
+    /// Store keeping the last `capacity` snapshots of every breakpoint
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, ..Default::default() }
+    }
+
+    /// Store a snapshot of breakpoint `number`, numbering its hit
+    pub fn push(&mut self, number: usize, mut snapshot: BreakPointSnapshot) {
+        let hits = self.hits.entry(number).or_default();
+        *hits += 1;
+        snapshot.hit = *hits;
+        let snapshots = self.snapshots.entry(number).or_default();
+        if snapshots.len() >= self.capacity.max(1) {
+            snapshots.pop_front();
+        }
+        snapshots.push_back(snapshot);
+    }
+
+    /// The last `limit` snapshots of breakpoint `number`, oldest first
+    pub fn last(&self, number: usize, limit: usize) -> Vec<BreakPointSnapshot> {
+        let Some(snapshots) = self.snapshots.get(&number) else {
+            return Vec::new();
+        };
+        snapshots.iter().skip(snapshots.len().saturating_sub(limit)).cloned().collect()
+    }
+}
+
+/// Outcome of setting exported breakpoints again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoredBreakPoints {
+    /// The breakpoints set
+    pub breakpoints: Vec<BreakPoint>,
+    /// The watchpoints set
+    pub watchpoints: Vec<Watchpoint>,
+    /// Locations that could not be set, with the reason
+    pub failed: Vec<String>,
 }
 
 impl std::ops::Deref for BreakPointSet {
@@ -193,13 +451,193 @@ impl std::ops::Deref for BreakPointSet {
     }
 }
 
+/// An async or stream record kept in the event history of a session
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionEvent {
+    /// Sequence number, increases monotonically within the session
+    pub seq: u64,
+    /// Reception time in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    /// Number of stops seen so far, the stop itself opens a new epoch
+    pub stop_epoch: u64,
+    /// One of exec, status, notify (async records), console, target, log
+    /// (stream records), logpoint (console output of a logpoint) or error
+    /// (output that could not be read or parsed)
+    pub kind: String,
+    /// Class of async records, e.g. stopped or breakpoint-created
+    pub class: Option<String>,
+    /// Decoded results of async records, raw for classes without a model
+    pub results: Option<AsyncEvent>,
+    /// Text of stream records
+    pub data: Option<String>,
+    /// Logpoint that printed the text of a logpoint event
+    pub logpoint: Option<String>,
+    /// Line of output that could not be parsed, of an error event
+    pub line: Option<String>,
+}
+
+impl SessionEvent {
+    /// Name of the MCP notification reporting this event, e.g. gdb/stopped,
+    /// gdb/breakpointModified or gdb/console
+    pub fn method(&self) -> String {
+        let name = self.class.as_deref().unwrap_or(&self.kind);
+        let mut method = String::from("gdb/");
+        let mut upper = false;
+        for c in name.chars() {
+            if c == '-' {
+                upper = true;
+            } else if upper {
+                method.push(c.to_ascii_uppercase());
+                upper = false;
+            } else {
+                method.push(c);
+            }
+        }
+        method
+    }
+}
+
+/// Payload of the notifications sent for the events of a session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionNotification {
+    /// Session the event belongs to
+    pub session_id: String,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+/// Bounded history of the async and stream records of a session, the oldest
+/// records are dropped once the capacity is reached
+#[derive(Debug)]
+pub struct EventHistory {
+    events: VecDeque<SessionEvent>,
+    capacity: usize,
+    next_seq: u64,
+    stop_epoch: u64,
+}
+
+impl EventHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { events: VecDeque::new(), capacity, next_seq: 0, stop_epoch: 0 }
+    }
+
+    /// Record an out of band record, with its results decoded if it's an
+    /// async record, and return the stored event
+    pub fn push(&mut self, record: &OutOfBandRecord, results: Option<AsyncEvent>) -> &SessionEvent {
+        let event = match record {
+            OutOfBandRecord::AsyncRecord { kind, class, .. } => {
+                if *class == AsyncClass::Stopped {
+                    self.stop_epoch += 1;
+                }
+                SessionEvent {
+                    kind: kind.to_string(),
+                    class: Some(class.to_string()),
+                    results,
+                    ..Default::default()
+                }
+            }
+            OutOfBandRecord::StreamRecord { kind, data } => SessionEvent {
+                kind: kind.to_string(),
+                data: Some(data.clone()),
+                ..Default::default()
+            },
+        };
+        self.insert(event)
+    }
+
+    /// Record an error of the output reader, e.g. a line that could not be
+    /// parsed, and return the stored event
+    pub fn push_error(&mut self, error: &AppError) -> &SessionEvent {
+        let line = match error {
+            AppError::ParseError { line, .. } => Some(line.clone()),
+            _ => None,
+        };
+        self.insert(SessionEvent {
+            kind: "error".to_string(),
+            data: Some(error.to_string()),
+            line,
+            ..Default::default()
+        })
+    }
+
+    /// Record a line printed by a logpoint and return the stored event
+    pub fn push_logpoint(&mut self, number: BreakPointNumber, text: &str) -> &SessionEvent {
+        self.insert(SessionEvent {
+            kind: "logpoint".to_string(),
+            data: Some(text.to_string()),
+            logpoint: Some(number.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// Number and store an event, dropping the oldest one if full
+    fn insert(&mut self, event: SessionEvent) -> &SessionEvent {
+        let event = SessionEvent {
+            seq: self.next_seq,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            stop_epoch: self.stop_epoch,
+            ..event
+        };
+        self.next_seq += 1;
+        if self.events.len() >= self.capacity.max(1) {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+        self.events.back().unwrap()
+    }
+
+    /// Events matching `kind` and `class` with a sequence number greater
+    /// than `since`, at most `limit` of them, oldest first
+    pub fn query(
+        &self,
+        kind: Option<&str>,
+        class: Option<&str>,
+        since: Option<u64>,
+        limit: usize,
+    ) -> Vec<SessionEvent> {
+        self.events
+            .iter()
+            .filter(|e| since.is_none_or(|since| e.seq > since))
+            .filter(|e| kind.is_none_or(|kind| e.kind == kind))
+            .filter(|e| class.is_none_or(|class| e.class.as_deref() == Some(class)))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Output of the logpoint `number` with a sequence number greater than
+    /// `since`, at most `limit` lines, oldest first
+    pub fn logpoint_output(
+        &self,
+        number: BreakPointNumber,
+        since: Option<u64>,
+        limit: usize,
+    ) -> Vec<SessionEvent> {
+        let number = number.to_string();
+        self.events
+            .iter()
+            .filter(|e| since.is_none_or(|since| e.seq > since))
+            .filter(|e| e.kind == "logpoint")
+            .filter(|e| e.logpoint.as_ref() == Some(&number))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
 /// Stack frame information
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StackFrame {
-    /// Frame level
+    /// Frame level, absent in stop records which always report the
+    /// innermost frame
     #[serde_as(as = "DisplayFromStr")]
+    #[serde(default)]
     pub level: u32,
     /// Function name
     #[serde(rename = "func")]
@@ -218,6 +656,443 @@ pub struct StackFrame {
     pub arch: Option<String>,
 }
 
+/// Why the target stopped, the `reason` of a `*stopped` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    BreakpointHit,
+    WatchpointTrigger,
+    ReadWatchpointTrigger,
+    AccessWatchpointTrigger,
+    WatchpointScope,
+    FunctionFinished,
+    LocationReached,
+    EndSteppingRange,
+    SignalReceived,
+    Exited,
+    ExitedNormally,
+    ExitedSignalled,
+    SolibEvent,
+    Fork,
+    Vfork,
+    Exec,
+    SyscallEntry,
+    SyscallReturn,
+    NoHistory,
+    /// A reason this server doesn't know about
+    Other(String),
+}
+
+impl StopReason {
+    /// The program is gone, it has to be started again
+    pub fn is_exit(&self) -> bool {
+        matches!(
+            self,
+            StopReason::Exited | StopReason::ExitedNormally | StopReason::ExitedSignalled
+        )
+    }
+}
+
+impl FromStr for StopReason {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "breakpoint-hit" => StopReason::BreakpointHit,
+            "watchpoint-trigger" => StopReason::WatchpointTrigger,
+            "read-watchpoint-trigger" => StopReason::ReadWatchpointTrigger,
+            "access-watchpoint-trigger" => StopReason::AccessWatchpointTrigger,
+            "watchpoint-scope" => StopReason::WatchpointScope,
+            "function-finished" => StopReason::FunctionFinished,
+            "location-reached" => StopReason::LocationReached,
+            "end-stepping-range" => StopReason::EndSteppingRange,
+            "signal-received" => StopReason::SignalReceived,
+            "exited" => StopReason::Exited,
+            "exited-normally" => StopReason::ExitedNormally,
+            "exited-signalled" => StopReason::ExitedSignalled,
+            "solib-event" => StopReason::SolibEvent,
+            "fork" => StopReason::Fork,
+            "vfork" => StopReason::Vfork,
+            "exec" => StopReason::Exec,
+            "syscall-entry" => StopReason::SyscallEntry,
+            "syscall-return" => StopReason::SyscallReturn,
+            "no-history" => StopReason::NoHistory,
+            other => StopReason::Other(other.to_string()),
+        })
+    }
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            StopReason::BreakpointHit => "breakpoint-hit",
+            StopReason::WatchpointTrigger => "watchpoint-trigger",
+            StopReason::ReadWatchpointTrigger => "read-watchpoint-trigger",
+            StopReason::AccessWatchpointTrigger => "access-watchpoint-trigger",
+            StopReason::WatchpointScope => "watchpoint-scope",
+            StopReason::FunctionFinished => "function-finished",
+            StopReason::LocationReached => "location-reached",
+            StopReason::EndSteppingRange => "end-stepping-range",
+            StopReason::SignalReceived => "signal-received",
+            StopReason::Exited => "exited",
+            StopReason::ExitedNormally => "exited-normally",
+            StopReason::ExitedSignalled => "exited-signalled",
+            StopReason::SolibEvent => "solib-event",
+            StopReason::Fork => "fork",
+            StopReason::Vfork => "vfork",
+            StopReason::Exec => "exec",
+            StopReason::SyscallEntry => "syscall-entry",
+            StopReason::SyscallReturn => "syscall-return",
+            StopReason::NoHistory => "no-history",
+            StopReason::Other(reason) => reason,
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// A watchpoint as reported when setting it and when it triggers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watchpoint {
+    pub number: BreakPointNumber,
+    /// The watched expression
+    #[serde(rename = "exp")]
+    pub expression: String,
+}
+
+/// Value of the watched expression when a watchpoint triggers
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchValue {
+    /// Value before the write
+    pub old: Option<String>,
+    /// Value after the write
+    pub new: Option<String>,
+    /// Value read, when the access didn't change it
+    pub value: Option<String>,
+}
+
+/// A disassembled instruction, as listed in the `asm_insns` of
+/// `-data-disassemble`
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instruction {
+    pub address: Address64,
+    #[serde(rename = "func-name")]
+    pub function: Option<String>,
+    /// Offset from the start of the function
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub offset: Option<u64>,
+    /// The instruction in the syntax of GDB, e.g. movl $0x0,-0x4(%rbp)
+    #[serde(rename = "inst")]
+    pub instruction: String,
+}
+
+/// A Rust panic, decoded when the target stops at a panic breakpoint
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RustPanic {
+    /// Panic message, only known if it has no formatted arguments
+    pub message: Option<String>,
+    /// Format string of a panic message with arguments, `{}` stands for the
+    /// arguments whose values can't be formatted from the debugger
+    pub message_template: Option<String>,
+    /// Source file the panic was raised in
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Summary of a `*stopped` async record
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopEvent {
+    /// Why the target stopped, e.g. breakpoint-hit or end-stepping-range
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub reason: Option<StopReason>,
+    /// Number of the breakpoint hit
+    #[serde(rename = "bkptno")]
+    pub breakpoint: Option<BreakPointNumber>,
+    /// Watchpoint triggered, reported as wpt, hw-rwpt or hw-awpt depending
+    /// on its mode
+    #[serde(rename = "wpt", alias = "hw-rwpt", alias = "hw-awpt")]
+    pub watchpoint: Option<Watchpoint>,
+    /// Value of the expression of the triggered watchpoint
+    pub value: Option<WatchValue>,
+    /// Number of the watchpoint that went out of scope
+    #[serde(rename = "wpnum")]
+    pub watchpoint_scope: Option<BreakPointNumber>,
+    /// Name of the received signal, e.g. SIGSEGV
+    #[serde(rename = "signal-name")]
+    pub signal_name: Option<String>,
+    /// Description of the received signal
+    #[serde(rename = "signal-meaning")]
+    pub signal_meaning: Option<String>,
+    /// Exit code of the program, only present if it exited
+    #[serde(rename = "exit-code")]
+    pub exit_code: Option<String>,
+    /// Value returned by the function run to its end by finish
+    #[serde(rename = "return-value")]
+    pub return_value: Option<String>,
+    /// Convenience variable holding the returned value, e.g. $1
+    #[serde(rename = "gdb-result-var")]
+    pub result_var: Option<String>,
+    /// Process id of the child of a fork or vfork
+    #[serde(rename = "newpid")]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub new_pid: Option<u32>,
+    /// Program started by an exec
+    #[serde(rename = "new-exec")]
+    pub new_exec: Option<String>,
+    /// Name of the system call entered or returned from
+    #[serde(rename = "syscall-name")]
+    pub syscall_name: Option<String>,
+    /// Number of the system call, reported when GDB doesn't know its name
+    #[serde(rename = "syscall-number")]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub syscall_number: Option<u32>,
+    /// Event caught if the target stopped at a catchpoint, filled in by
+    /// the server from the breakpoint hit
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub catchpoint: Option<CatchEvent>,
+    /// Panic raised if the target stopped at a panic breakpoint, filled in
+    /// by the server
+    pub panic: Option<RustPanic>,
+    /// Instruction the target stopped at, filled in by the server after
+    /// instruction steps and reverse execution
+    pub instruction: Option<Instruction>,
+    /// Thread that caused the stop
+    #[serde(rename = "thread-id")]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub thread_id: Option<u32>,
+    /// Frame the target stopped at, not present if the program exited
+    pub frame: Option<StackFrame>,
+}
+
+/// Content of a `*running` async record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningEvent {
+    /// Thread that resumed, or all
+    #[serde(rename = "thread-id")]
+    pub thread_id: String,
+}
+
+/// Content of the `=thread-*` and `=thread-group-*` async records
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThreadEvent {
+    /// Thread id, or thread group id (e.g. i1) for thread group records
+    pub id: String,
+    /// Thread group of a created or exited thread
+    pub group_id: Option<String>,
+    /// Process id of a started thread group
+    pub pid: Option<String>,
+    /// Exit code of an exited thread group
+    pub exit_code: Option<String>,
+    /// Frame of a selected thread
+    pub frame: Option<StackFrame>,
+}
+
+/// Address range of a loaded library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressRange {
+    pub from: Address64,
+    pub to: Address64,
+}
+
+/// Content of the `=library-loaded` and `=library-unloaded` async records
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LibraryLoaded {
+    pub id: String,
+    /// Name of the library on the target
+    pub target_name: String,
+    /// Name of the library on the host
+    pub host_name: String,
+    /// Whether the debug symbols are loaded, only for loaded libraries
+    pub symbols_loaded: Option<String>,
+    pub thread_group: Option<String>,
+    /// Address ranges the library is mapped at
+    #[serde(default)]
+    pub ranges: Vec<AddressRange>,
+}
+
+/// Content of the `=breakpoint-*` async records, created and modified records
+/// carry the breakpoint, deleted records only its number
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakpointEvent {
+    pub bkpt: Option<BreakPoint>,
+    pub id: Option<BreakPointNumber>,
+}
+
+/// Content of a `=cmd-param-changed` async record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CmdParamChanged {
+    /// Parameter set with the set command, e.g. print pretty
+    pub param: String,
+    pub value: String,
+}
+
+/// Content of a `=memory-changed` async record
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MemoryChanged {
+    pub thread_group: String,
+    #[serde(rename = "addr")]
+    pub address: Address64,
+    /// Number of bytes changed
+    pub len: String,
+    /// Set to code if the memory contains code
+    pub r#type: Option<String>,
+}
+
+/// Content of a `=register-changed` async record
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterChanged {
+    pub name: String,
+    pub value: String,
+    pub group: Option<String>,
+    pub frame: Option<String>,
+}
+
+/// Content of the `=tsv-*` async records about trace state variables
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceStateVariable {
+    /// Name of the variable, absent when all of them were deleted
+    pub name: Option<String>,
+    pub initial: Option<String>,
+    pub current: Option<String>,
+}
+
+/// Content of the `=record-started` and `=record-stopped` async records
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RecordStarted {
+    pub thread_group: String,
+    /// Record method, e.g. full or btrace, only when started
+    pub method: Option<String>,
+    /// Record format of the btrace method
+    pub format: Option<String>,
+}
+
+/// Typed content of an async record
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AsyncEvent {
+    Running(RunningEvent),
+    Stopped(Box<StopEvent>),
+    Thread(ThreadEvent),
+    Library(LibraryLoaded),
+    Breakpoint(Box<BreakpointEvent>),
+    CmdParamChanged(CmdParamChanged),
+    MemoryChanged(MemoryChanged),
+    RegisterChanged(RegisterChanged),
+    Tsv(TraceStateVariable),
+    Record(RecordStarted),
+    /// Classes without a model, e.g. traceframe-changed, keep their results
+    Other(serde_json::Value),
+}
+
+impl AsyncEvent {
+    /// Decode the results of an async record according to its class
+    pub fn decode(class: &AsyncClass, results: &serde_json::Value) -> Result<Self, AppError> {
+        let results = results.clone();
+        Ok(match class {
+            AsyncClass::Running => AsyncEvent::Running(serde_json::from_value(results)?),
+            AsyncClass::Stopped => AsyncEvent::Stopped(serde_json::from_value(results)?),
+            AsyncClass::Thread(_) => AsyncEvent::Thread(serde_json::from_value(results)?),
+            AsyncClass::LibraryLoaded | AsyncClass::LibraryUnloaded => {
+                AsyncEvent::Library(serde_json::from_value(results)?)
+            }
+            AsyncClass::BreakPoint(_) => AsyncEvent::Breakpoint(serde_json::from_value(results)?),
+            AsyncClass::CmdParamChanged => {
+                AsyncEvent::CmdParamChanged(serde_json::from_value(results)?)
+            }
+            AsyncClass::MemoryChanged => {
+                AsyncEvent::MemoryChanged(serde_json::from_value(results)?)
+            }
+            AsyncClass::RegisterChanged => {
+                AsyncEvent::RegisterChanged(serde_json::from_value(results)?)
+            }
+            AsyncClass::Tsv(_) => AsyncEvent::Tsv(serde_json::from_value(results)?),
+            AsyncClass::Record(_) => AsyncEvent::Record(serde_json::from_value(results)?),
+            AsyncClass::TraceframeChanged | AsyncClass::Other(_) => AsyncEvent::Other(results),
+        })
+    }
+}
+
+/// Outcome of an execution command
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ExecutionOutcome {
+    /// The target is still running, holds the results of the command
+    Running(serde_json::Value),
+    /// The target stopped again
+    Stopped(Box<StopEvent>),
+}
+
+/// Condition ending a repeated step
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepCondition {
+    /// The expression is true, i.e. not zero, false or a null pointer
+    Expression(String),
+    /// The source line is another one than before the first step
+    LineChanged,
+    /// The function is another one than before the first step, e.g. it
+    /// returned or a call was stepped into
+    FunctionChanged,
+}
+
+/// How often a step is repeated, it ends early if the target stops for
+/// another reason, e.g. at a breakpoint
+#[derive(Debug, Clone, Default)]
+pub struct StepRepeat {
+    /// Number of steps, unless the condition holds earlier
+    pub count: Option<usize>,
+    /// Step until this holds
+    pub until: Option<StepCondition>,
+    /// Guard against conditions never holding, the configured one by default
+    pub max_steps: Option<usize>,
+}
+
+/// Why a repeated step ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StepEnd {
+    /// All the steps asked for were made
+    Count,
+    /// The condition holds
+    Condition,
+    /// The most steps allowed were made before the condition held
+    MaxSteps,
+    /// The target stopped for another reason, e.g. a breakpoint or its exit
+    Stopped,
+    /// The target didn't stop within the timeout
+    Running,
+}
+
+/// Outcome of a repeated step
+#[derive(Debug, Clone, Serialize)]
+pub struct SteppingOutcome {
+    /// Number of steps made
+    pub steps: usize,
+    pub end: StepEnd,
+    /// Outcome of the last step
+    pub last: ExecutionOutcome,
+}
+
 pub enum PrintValue {
     /// print only the names of the variables, equivalent to "--no-values"
     NoValues,
@@ -259,6 +1134,114 @@ pub struct Variable {
     pub value: Option<String>,
 }
 
+/// Value of an evaluated expression, read from the variable object created
+/// for it
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpressionValue {
+    /// Name of the variable object, not reported as it's deleted right away
+    #[serde(skip_serializing)]
+    pub name: String,
+    pub value: Option<String>,
+    pub r#type: Option<String>,
+    /// Number of children, e.g. the fields of a struct
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(rename = "numchild")]
+    pub children: Option<u32>,
+}
+
+/// Whether a thread is running or stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadState {
+    Stopped,
+    Running,
+}
+
+/// A thread of the inferior, an entry of `-thread-info`
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Thread {
+    /// Global thread id, used to select the thread
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: u32,
+    /// Target's name of the thread, e.g. `Thread 0x7ffff7d8a740 (LWP 1234)`
+    pub target_id: String,
+    /// Name given to the thread by the program
+    pub name: Option<String>,
+    /// Extra target information, e.g. the RTOS task state
+    pub details: Option<String>,
+    pub state: ThreadState,
+    /// Processor core the thread last ran on
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub core: Option<u32>,
+    /// Current frame, only known while the thread is stopped
+    pub frame: Option<StackFrame>,
+}
+
+/// The threads of the inferior and the selected one
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThreadList {
+    pub threads: Vec<Thread>,
+    /// Selected thread, absent if there is none
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub current_thread_id: Option<u32>,
+}
+
+/// Threads sharing an identical stack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktraceGroup {
+    /// Number of threads with this stack
+    pub count: usize,
+    pub thread_ids: Vec<u32>,
+    pub frames: Vec<StackFrame>,
+}
+
+impl BacktraceGroup {
+    /// Group the stacks of threads, two stacks are identical if their frames
+    /// have the same functions and addresses. The groups are ordered by
+    /// decreasing count, then in the order of their first thread.
+    pub fn group(stacks: Vec<(u32, Vec<StackFrame>)>) -> Vec<BacktraceGroup> {
+        let mut groups: Vec<BacktraceGroup> = Vec::new();
+        let mut index: HashMap<_, usize> = HashMap::new();
+        for (thread_id, frames) in stacks {
+            let key: Vec<_> =
+                frames.iter().map(|frame| (frame.function.clone(), frame.address)).collect();
+            match index.get(&key).copied() {
+                Some(i) => {
+                    groups[i].count += 1;
+                    groups[i].thread_ids.push(thread_id);
+                }
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push(BacktraceGroup { count: 1, thread_ids: vec![thread_id], frames });
+                }
+            }
+        }
+        groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+        groups
+    }
+}
+
+/// Backtraces of all threads, threads with identical stacks grouped together
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllBacktraces {
+    /// Number of threads of the inferior
+    pub threads: usize,
+    pub groups: Vec<BacktraceGroup>,
+    /// Running threads, whose stack can't be read
+    pub running: Option<Vec<u32>>,
+    /// Errors reading the stack of a thread
+    pub errors: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub enum RegisterRaw {
     U32(Address32),
@@ -589,4 +1572,221 @@ mod test {
             ))
         );
     }
+
+    #[test]
+    fn test_stop_event() {
+        let stop: StopEvent = serde_json::from_str(
+            "{\"reason\":\"breakpoint-hit\",\"disp\":\"keep\",\"bkptno\":\"1\",\"frame\":{\
+            \"addr\":\"0x000055555557003f\",\"func\":\"test_app::main\",\"args\":[],\
+            \"file\":\"src/bin/test_app.rs\",\"fullname\":\"/src/bin/test_app.rs\",\"line\":\"5\",\
+            \"arch\":\"i386:x86-64\"},\"thread-id\":\"1\",\"stopped-threads\":\"all\",\"core\":\"6\"}",
+        )
+        .unwrap();
+        assert_eq!(stop.reason, Some(StopReason::BreakpointHit));
+        assert_eq!(stop.breakpoint, Some(BreakPointNumber { major: 1, minor: None }));
+        assert_eq!(stop.thread_id, Some(1));
+        let frame = stop.frame.unwrap();
+        assert_eq!(frame.level, 0);
+        assert_eq!(frame.line, Some(5));
+        assert_eq!(frame.address, Some(Address(0x55555557003f)));
+    }
+
+    #[test]
+    fn test_group_backtraces() {
+        let frame = |function: &str, address| StackFrame {
+            level: 0,
+            function: function.to_string(),
+            file: None,
+            fullname: None,
+            line: None,
+            address: Some(Address(address)),
+            arch: None,
+        };
+        let waiting = vec![frame("pthread_cond_wait", 0x10), frame("worker", 0x20)];
+        let groups = BacktraceGroup::group(vec![
+            (1, vec![frame("main", 0x30)]),
+            (2, waiting.clone()),
+            (3, vec![frame("pthread_cond_wait", 0x10), frame("worker", 0x24)]),
+            (4, waiting.clone()),
+            (5, waiting),
+        ]);
+        let threads: Vec<_> = groups.iter().map(|g| (g.count, g.thread_ids.clone())).collect();
+        assert_eq!(threads, vec![(3, vec![2, 4, 5]), (1, vec![1]), (1, vec![3])]);
+        assert_eq!(groups[0].frames[1].function, "worker");
+    }
+
+    #[test]
+    fn test_watchpoint_stop() {
+        let stop: StopEvent = serde_json::from_str(
+            "{\"reason\":\"read-watchpoint-trigger\",\"hw-rwpt\":{\"number\":\"3\",\"exp\":\"flags\"},\
+            \"value\":{\"value\":\"4\"},\"thread-id\":\"1\"}",
+        )
+        .unwrap();
+        assert_eq!(stop.reason, Some(StopReason::ReadWatchpointTrigger));
+        assert_eq!(stop.watchpoint.unwrap().number, BreakPointNumber { major: 3, minor: None });
+        assert_eq!(stop.value.unwrap().value.as_deref(), Some("4"));
+
+        let stop: StopEvent =
+            serde_json::from_str("{\"reason\":\"watchpoint-scope\",\"wpnum\":\"2\"}").unwrap();
+        assert_eq!(stop.watchpoint_scope, Some(BreakPointNumber { major: 2, minor: None }));
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let conditional: BreakPoint = serde_json::from_str(
+            "{\"number\":\"2\",\"type\":\"breakpoint\",\"disp\":\"del\",\"enabled\":\"y\",\
+            \"addr\":\"0x0000000000001139\",\"func\":\"main\",\"file\":\"main.c\",\
+            \"fullname\":\"/src/main.c\",\"line\":\"5\",\"thread-groups\":[\"i1\"],\
+            \"cond\":\"argc > 1\",\"thread\":\"1\",\"times\":\"3\",\"ignore\":\"2\",\
+            \"original-location\":\"main\"}",
+        )
+        .unwrap();
+        assert_eq!(conditional.address, Some(Address(0x1139)));
+        assert_eq!(conditional.function.as_deref(), Some("main"));
+        assert_eq!(conditional.condition.as_deref(), Some("argc > 1"));
+        assert_eq!(conditional.times, Some(3));
+        assert_eq!(conditional.ignore_count, Some(2));
+        assert_eq!(conditional.thread, Some(1));
+        assert_eq!(conditional.src_pos.unwrap().line, 5);
+
+        let multiple: BreakPoint = serde_json::from_str(
+            "{\"number\":\"3\",\"type\":\"breakpoint\",\"disp\":\"keep\",\"enabled\":\"y\",\
+            \"addr\":\"<MULTIPLE>\",\"times\":\"0\",\"original-location\":\"lib.c:6\",\
+            \"locations\":[{\"number\":\"3.1\",\"enabled\":\"y\",\"addr\":\"0x1bcec\",\
+            \"func\":\"f\",\"file\":\"lib.c\",\"fullname\":\"/src/lib.c\",\"line\":\"6\"},\
+            {\"number\":\"3.2\",\"enabled\":\"n\",\"addr\":\"0x21618\",\"func\":\"g\"}]}",
+        )
+        .unwrap();
+        assert_eq!(multiple.address, None);
+        assert!(multiple.src_pos.is_none());
+        let locations = multiple.locations.unwrap();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[1].number, BreakPointNumber { major: 3, minor: Some(2) });
+        assert_eq!(locations[1].address, Some(Address(0x21618)));
+        assert!(locations[1].src_pos.is_none());
+
+        let pending: BreakPoint = serde_json::from_str(
+            "{\"number\":\"4\",\"type\":\"breakpoint\",\"disp\":\"keep\",\"enabled\":\"y\",\
+            \"addr\":\"<PENDING>\",\"pending\":\"plugin_init\",\"times\":\"0\"}",
+        )
+        .unwrap();
+        assert_eq!(pending.address, None);
+        assert_eq!(pending.pending.as_deref(), Some("plugin_init"));
+    }
+
+    #[test]
+    fn test_event_history() {
+        let mut history = EventHistory::new(3);
+        let stream = OutOfBandRecord::StreamRecord {
+            kind: crate::mi::output::StreamKind::Console,
+            data: "hello".to_string(),
+        };
+        let stopped = OutOfBandRecord::AsyncRecord {
+            token: None,
+            kind: crate::mi::output::AsyncKind::Exec,
+            class: AsyncClass::Stopped,
+            results: serde_json::json!({"reason": "end-stepping-range"}),
+        };
+        let decoded = |record: &OutOfBandRecord| match record {
+            OutOfBandRecord::AsyncRecord { class, results, .. } => {
+                Some(AsyncEvent::decode(class, results).unwrap())
+            }
+            OutOfBandRecord::StreamRecord { .. } => None,
+        };
+        history.push(&stream, None);
+        assert_eq!(history.push(&stopped, decoded(&stopped)).stop_epoch, 1);
+        history.push(&stream, None);
+        history.push(&stopped, decoded(&stopped));
+
+        // the oldest event is dropped
+        let all = history.query(None, None, None, 10);
+        assert_eq!(all.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let stops = history.query(Some("exec"), Some("stopped"), None, 10);
+        assert_eq!(stops.iter().map(|e| e.stop_epoch).collect::<Vec<_>>(), vec![1, 2]);
+
+        let page = history.query(None, None, Some(1), 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].data.as_deref(), Some("hello"));
+        assert_eq!(page[0].method(), "gdb/console");
+        assert_eq!(stops[0].method(), "gdb/stopped");
+        match &stops[0].results {
+            Some(AsyncEvent::Stopped(stop)) => {
+                assert_eq!(stop.reason, Some(StopReason::EndSteppingRange))
+            }
+            other => panic!("stop not decoded: {:?}", other),
+        }
+
+        let modified = OutOfBandRecord::AsyncRecord {
+            token: None,
+            kind: crate::mi::output::AsyncKind::Notify,
+            class: AsyncClass::BreakPoint(crate::mi::output::BreakPointEvent::Modified),
+            results: serde_json::json!({}),
+        };
+        assert_eq!(history.push(&modified, None).method(), "gdb/breakpointModified");
+    }
+
+    #[test]
+    fn test_stop_reason() {
+        for reason in ["exited-normally", "syscall-entry", "new-reason"] {
+            assert_eq!(reason.parse::<StopReason>().unwrap().to_string(), reason);
+        }
+        assert!("exited-signalled".parse::<StopReason>().unwrap().is_exit());
+        assert_eq!("solib-event".parse::<StopReason>(), Ok(StopReason::SolibEvent));
+        assert_eq!(
+            "new-reason".parse::<StopReason>(),
+            Ok(StopReason::Other("new-reason".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_async_event() {
+        use crate::mi::output::{BreakPointEvent, ThreadEvent as ThreadClass};
+
+        let library = serde_json::json!({
+            "id": "/lib/libc.so.6", "target-name": "/lib/libc.so.6", "host-name": "/lib/libc.so.6",
+            "symbols-loaded": "0", "thread-group": "i1",
+            "ranges": [{"from": "0x00007ffff7c28800", "to": "0x00007ffff7dbd93d"}]
+        });
+        match AsyncEvent::decode(&AsyncClass::LibraryLoaded, &library).unwrap() {
+            AsyncEvent::Library(library) => {
+                assert_eq!(library.thread_group.as_deref(), Some("i1"));
+                assert_eq!(library.ranges[0].from, Address(0x7ffff7c28800));
+            }
+            other => panic!("not a library event: {:?}", other),
+        }
+
+        let created = serde_json::json!({"bkpt": {
+            "number": "1", "type": "breakpoint", "disp": "keep", "enabled": "y",
+            "addr": "0x0000000000001139", "func": "main", "file": "main.c",
+            "fullname": "/src/main.c", "line": "3", "times": "0"
+        }});
+        let class = AsyncClass::BreakPoint(BreakPointEvent::Created);
+        match AsyncEvent::decode(&class, &created).unwrap() {
+            AsyncEvent::Breakpoint(event) => assert_eq!(event.bkpt.unwrap().number.major, 1),
+            other => panic!("not a breakpoint event: {:?}", other),
+        }
+        let class = AsyncClass::BreakPoint(BreakPointEvent::Deleted);
+        match AsyncEvent::decode(&class, &serde_json::json!({"id": "2"})).unwrap() {
+            AsyncEvent::Breakpoint(event) => {
+                assert_eq!(event.id, Some(BreakPointNumber { major: 2, minor: None }))
+            }
+            other => panic!("not a breakpoint event: {:?}", other),
+        }
+
+        let exited = serde_json::json!({"id": "i1", "exit-code": "0"});
+        let class = AsyncClass::Thread(ThreadClass::GroupExited);
+        match AsyncEvent::decode(&class, &exited).unwrap() {
+            AsyncEvent::Thread(thread) => assert_eq!(thread.exit_code.as_deref(), Some("0")),
+            other => panic!("not a thread event: {:?}", other),
+        }
+
+        let changed = serde_json::json!({"thread-group": "i1", "addr": "0x601040", "len": "0x4"});
+        match AsyncEvent::decode(&AsyncClass::MemoryChanged, &changed).unwrap() {
+            AsyncEvent::MemoryChanged(changed) => assert_eq!(changed.address, Address(0x601040)),
+            other => panic!("not a memory event: {:?}", other),
+        }
+
+        assert!(AsyncEvent::decode(&AsyncClass::Running, &serde_json::json!({})).is_err());
+    }
 }
@@ -1,5 +1,5 @@
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, LazyLock};
 
 use anyhow::Result;
@@ -9,7 +9,12 @@ use mcp_core_macros::tool;
 use schemars::{JsonSchema, schema::Schema, schema::SchemaObject};
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
 use crate::gdb::GDBManager;
+use crate::mi::commands::{
+    BreakPointLocation, BreakPointOptions, CatchEvent, ValueFormat, WatchMode,
+};
+use crate::models::{BreakPointActions, StepCondition, StepRepeat};
 
 /// A positive integer type that generates clean JSON Schema without format specifiers
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -140,6 +145,41 @@ pub async fn get_all_sessions_tool() -> Result<ToolResponseContent> {
     Ok(tool_text_content!(format!("Sessions: {}", serde_json::to_string(&sessions)?)))
 }
 
+#[tool(
+    name = "get_session_events",
+    description = "Get the recorded GDB events of a session, oldest first. Each event has a \
+                   sequence number (seq), a timestamp in milliseconds, the stop epoch (number \
+                   of stops seen so far), its kind and either the async class and decoded \
+                   results or the stream text",
+    params(
+        session_id = "The ID of the GDB session",
+        kind = "if provided, only return events of this kind: exec, status, notify, \
+                console, target or log",
+        class = "if provided, only return async events of this class, e.g. stopped, \
+                 running, breakpoint-created or thread-created",
+        since = "if provided, only return events with a sequence number greater than this",
+        limit = "if provided, maximum number of events to return, defaults to 100"
+    )
+)]
+pub async fn get_session_events_tool(
+    session_id: String,
+    kind: Option<String>,
+    class: Option<String>,
+    since: Option<PositiveInt>,
+    limit: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let events = GDB_MANAGER
+        .get_session_events(
+            &session_id,
+            kind,
+            class,
+            since.map(|v| v.0 as u64),
+            limit.map(|v| v.0 as usize),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Events: {}", serde_json::to_string(&events)?)))
+}
+
 #[tool(
     name = "close_session",
     description = "Close a GDB debugging session",
@@ -153,51 +193,284 @@ pub async fn close_session_tool(session_id: String) -> Result<ToolResponseConten
 #[tool(
     name = "start_debugging",
     description = "Start debugging in a session",
-    params(session_id = "The ID of the GDB session")
+    params(
+        session_id = "The ID of the GDB session",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
 )]
-pub async fn start_debugging_tool(session_id: String) -> Result<ToolResponseContent> {
-    let ret = GDB_MANAGER.start_debugging(&session_id).await?;
-    Ok(tool_text_content!(format!("Started debugging: {}", ret)))
+pub async fn start_debugging_tool(
+    session_id: String,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let ret = GDB_MANAGER
+        .start_debugging(&session_id, wait.unwrap_or(false), timeout.map(|v| v.0 as u64))
+        .await?;
+    Ok(tool_text_content!(format!("Started debugging: {}", serde_json::to_string(&ret)?)))
 }
 
 #[tool(
     name = "stop_debugging",
-    description = "Stop debugging in a session",
+    description = "Stop debugging in a session, interrupts the running program and \
+                   returns the stack frame where it stopped",
     params(session_id = "The ID of the GDB session")
 )]
 pub async fn stop_debugging_tool(session_id: String) -> Result<ToolResponseContent> {
-    let ret = GDB_MANAGER.stop_debugging(&session_id).await?;
-    Ok(tool_text_content!(format!("Stopped debugging: {}", ret)))
+    let frame = GDB_MANAGER.stop_debugging(&session_id).await?;
+    Ok(tool_text_content!(format!("Stopped debugging: {}", serde_json::to_string(&frame)?)))
+}
+
+#[tool(
+    name = "wait_for_stop",
+    description = "Wait until the program stops, returns the stop event with the reason, \
+                   breakpoint number, signal, thread ID and frame. Use it after \
+                   continue_execution to react only to the events of interest",
+    params(
+        session_id = "The ID of the GDB session",
+        reasons = "if provided, only report stops with one of these reasons: breakpoint-hit, \
+                   watchpoint-trigger, signal-received, exited-normally, end-stepping-range, \
+                   ... Other stops are skipped without resuming the program, exits are \
+                   always reported",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn wait_for_stop_tool(
+    session_id: String,
+    reasons: Option<Vec<String>>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let stopped =
+        GDB_MANAGER.wait_for_stop(&session_id, reasons, timeout.map(|v| v.0 as u64)).await?;
+    Ok(tool_text_content!(format!("Stopped: {}", serde_json::to_string(&stopped)?)))
 }
 
 #[tool(
     name = "get_breakpoints",
-    description = "Get all breakpoints in the current GDB session",
+    description = "Get all breakpoints and watchpoints in the current GDB session",
     params(session_id = "The ID of the GDB session")
 )]
 pub async fn get_breakpoints_tool(session_id: String) -> Result<ToolResponseContent> {
-    let breakpoints = GDB_MANAGER.get_breakpoints(&session_id).await?;
-    Ok(tool_text_content!(format!("Breakpoints: {}", serde_json::to_string(&breakpoints)?)))
+    let (watchpoints, breakpoints): (Vec<_>, Vec<_>) = GDB_MANAGER
+        .get_breakpoints(&session_id)
+        .await?
+        .into_iter()
+        .partition(|breakpoint| breakpoint.watch_mode().is_some());
+    Ok(tool_text_content!(format!(
+        "Breakpoints: {}\nWatchpoints: {}",
+        serde_json::to_string(&breakpoints)?,
+        serde_json::to_string(&watchpoints)?
+    )))
+}
+
+/// Location of a breakpoint given as file and line, function (optionally of
+/// a file) or address
+fn breakpoint_location<'a>(
+    file: Option<&'a Path>,
+    line: Option<PositiveInt>,
+    function: Option<&'a str>,
+    address: Option<&str>,
+) -> Result<BreakPointLocation<'a>, AppError> {
+    match (address, function, line) {
+        (Some(address), None, None) if file.is_none() => {
+            let digits = address.strip_prefix("0x").unwrap_or(address);
+            let address = usize::from_str_radix(digits, 16).map_err(|_| {
+                AppError::InvalidArgument(format!("invalid breakpoint address {}", address))
+            })?;
+            Ok(BreakPointLocation::Address(address))
+        }
+        (None, Some(function), None) => Ok(BreakPointLocation::Function(file, function)),
+        (None, None, Some(line)) => match file {
+            Some(file) => Ok(BreakPointLocation::Line(file, line.0 as usize)),
+            None => Err(AppError::InvalidArgument("line requires a file".to_string())),
+        },
+        _ => Err(AppError::InvalidArgument(
+            "set either file and line, function or address".to_string(),
+        )),
+    }
 }
 
 #[tool(
     name = "set_breakpoint",
-    description = "Set a breakpoint in the code",
+    description = "Set a breakpoint in the code, at a source line (file and line), a function \
+                   (function, optionally with file) or an address",
     params(
         session_id = "The ID of the GDB session",
-        file = "Source file path",
-        line = "Line number"
+        file = "Source file path, with line for a source line, or with function to pick the \
+                function of this file",
+        line = "Line number in file",
+        function = "Function name",
+        address = "Instruction address, as hexadecimal literal, e.g. 0x401136",
+        condition = "if provided, only stop when this expression is true",
+        ignore_count = "if provided, ignore this many hits before stopping",
+        temporary = "if true, delete the breakpoint after the first hit",
+        hardware = "if true, insert a hardware breakpoint",
+        pending = "if true, keep the breakpoint pending when the location can't be found yet, \
+                   e.g. in a shared library loaded later",
+        thread_id = "if provided, only stop in this thread"
     )
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn set_breakpoint_tool(
     session_id: String,
-    file: String,
-    line: PositiveInt,
+    file: Option<String>,
+    line: Option<PositiveInt>,
+    function: Option<String>,
+    address: Option<String>,
+    condition: Option<String>,
+    ignore_count: Option<PositiveInt>,
+    temporary: Option<bool>,
+    hardware: Option<bool>,
+    pending: Option<bool>,
+    thread_id: Option<PositiveInt>,
 ) -> Result<ToolResponseContent> {
-    let breakpoint = GDB_MANAGER.set_breakpoint(&session_id, &PathBuf::from(file), line.0 as usize).await?;
+    let file = file.map(PathBuf::from);
+    let location =
+        breakpoint_location(file.as_deref(), line, function.as_deref(), address.as_deref())?;
+    let options = BreakPointOptions {
+        condition,
+        ignore_count: ignore_count.map(|v| v.0 as usize),
+        temporary: temporary.unwrap_or(false),
+        hardware: hardware.unwrap_or(false),
+        pending: pending.unwrap_or(false),
+        thread: thread_id.map(|v| v.0 as usize),
+    };
+    let breakpoint = GDB_MANAGER.set_breakpoint(&session_id, location, &options).await?;
     Ok(tool_text_content!(format!("Set breakpoint: {}", serde_json::to_string(&breakpoint)?)))
 }
 
+#[tool(
+    name = "set_logpoint",
+    description = "Set a logpoint, a dynamic printf printing a message every time the program \
+                   passes a location without stopping it. The output is collected in the event \
+                   history, get it with get_logpoint_output",
+    params(
+        session_id = "The ID of the GDB session",
+        file = "Source file path, with line for a source line, or with function to pick the \
+                function of this file",
+        line = "Line number in file",
+        function = "Function name",
+        address = "Instruction address, as hexadecimal literal, e.g. 0x401136",
+        format = "printf format string of the message, e.g. \"counter=%d\\n\"",
+        arguments = "if provided, the array of expressions formatted by the format string",
+        condition = "if provided, only log when this expression is true"
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_logpoint_tool(
+    session_id: String,
+    file: Option<String>,
+    line: Option<PositiveInt>,
+    function: Option<String>,
+    address: Option<String>,
+    format: String,
+    arguments: Option<Vec<String>>,
+    condition: Option<String>,
+) -> Result<ToolResponseContent> {
+    let file = file.map(PathBuf::from);
+    let location =
+        breakpoint_location(file.as_deref(), line, function.as_deref(), address.as_deref())?;
+    let options = BreakPointOptions { condition, ..Default::default() };
+    let logpoint = GDB_MANAGER
+        .set_logpoint(&session_id, location, &options, &format, &arguments.unwrap_or_default())
+        .await?;
+    Ok(tool_text_content!(format!("Set logpoint: {}", serde_json::to_string(&logpoint)?)))
+}
+
+#[tool(
+    name = "get_logpoint_output",
+    description = "Get the messages printed by a logpoint, oldest first",
+    params(
+        session_id = "The ID of the GDB session",
+        logpoint = "The number of the logpoint",
+        since = "if provided, only messages with a sequence number greater than this",
+        limit = "if provided, at most this many messages (default 100)"
+    )
+)]
+pub async fn get_logpoint_output_tool(
+    session_id: String,
+    logpoint: String,
+    since: Option<PositiveInt>,
+    limit: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let output = GDB_MANAGER
+        .get_logpoint_output(
+            &session_id,
+            &logpoint,
+            since.map(|v| v.0 as u64),
+            limit.map(|v| v.0 as usize),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Logpoint output: {}", serde_json::to_string(&output)?)))
+}
+
+#[tool(
+    name = "set_watchpoint",
+    description = "Set a watchpoint, the program stops when an expression is written, read or \
+                   accessed. The stop reports the old and new value. Hardware watchpoints are \
+                   scarce on most targets, e.g. 4 on Cortex-M",
+    params(
+        session_id = "The ID of the GDB session",
+        expression = "The expression to watch, e.g. a variable or *(int *)0x20000000",
+        mode = "if provided, what to watch for: write (default), read or access"
+    )
+)]
+pub async fn set_watchpoint_tool(
+    session_id: String,
+    expression: String,
+    mode: Option<String>,
+) -> Result<ToolResponseContent> {
+    let mode = mode
+        .map(|m| m.parse::<WatchMode>())
+        .transpose()
+        .map_err(AppError::InvalidArgument)?
+        .unwrap_or(WatchMode::Write);
+    let watchpoint = GDB_MANAGER.set_watchpoint(&session_id, &expression, mode).await?;
+    Ok(tool_text_content!(format!("Set watchpoint: {}", serde_json::to_string(&watchpoint)?)))
+}
+
+#[tool(
+    name = "set_catchpoint",
+    description = "Set a catchpoint, the program stops when a C++ exception is thrown, caught or \
+                   rethrown, on a system call, fork, vfork or exec, or when a signal is received. \
+                   The stop reports the event caught, e.g. the system call or the signal",
+    params(
+        session_id = "The ID of the GDB session",
+        event = "The event to catch: throw, catch, rethrow, syscall, fork, vfork, exec or signal",
+        filter = "if provided, a regex on the exception type for throw, catch and rethrow, the \
+                  system calls (names, numbers or groups like g:network) or signals (names, \
+                  numbers or all) to catch separated by spaces for syscall and signal",
+        temporary = "if provided and true, the catchpoint is deleted after it is hit once"
+    )
+)]
+pub async fn set_catchpoint_tool(
+    session_id: String,
+    event: String,
+    filter: Option<String>,
+    temporary: Option<bool>,
+) -> Result<ToolResponseContent> {
+    let event = event.parse::<CatchEvent>().map_err(AppError::InvalidArgument)?;
+    let catchpoint = GDB_MANAGER
+        .set_catchpoint(&session_id, event, filter.as_deref(), temporary.unwrap_or(false))
+        .await?;
+    Ok(tool_text_content!(format!("Set catchpoint: {}", serde_json::to_string(&catchpoint)?)))
+}
+
+#[tool(
+    name = "catch_rust_panic",
+    description = "Stop the program when it panics, at rust_begin_unwind or rust_panic. The stop \
+                   reports the location the panic was raised at and its message. Formatted \
+                   arguments can't be read from the debugger, a message with arguments is \
+                   reported as message_template with {} in place of each argument",
+    params(session_id = "The ID of the GDB session")
+)]
+pub async fn catch_rust_panic_tool(session_id: String) -> Result<ToolResponseContent> {
+    let breakpoint = GDB_MANAGER.catch_rust_panic(&session_id).await?;
+    Ok(tool_text_content!(format!("Set panic breakpoint: {}", serde_json::to_string(&breakpoint)?)))
+}
+
 #[tool(
     name = "delete_breakpoint",
     description = "Delete one or more breakpoints in the code",
@@ -214,45 +487,309 @@ pub async fn delete_breakpoint_tool(
     Ok(tool_text_content!("Breakpoints deleted".to_string()))
 }
 
+#[tool(
+    name = "enable_breakpoints",
+    description = "Enable one or more breakpoints, keeping their numbers and hit counts",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoints = "The array of the breakpoint numbers to enable, major.minor (e.g. 2.1) \
+                       enables a single location of a breakpoint with several locations"
+    )
+)]
+pub async fn enable_breakpoints_tool(
+    session_id: String,
+    breakpoints: Vec<String>,
+) -> Result<ToolResponseContent> {
+    GDB_MANAGER.enable_breakpoints(&session_id, breakpoints).await?;
+    Ok(tool_text_content!("Breakpoints enabled".to_string()))
+}
+
+#[tool(
+    name = "disable_breakpoints",
+    description = "Disable one or more breakpoints without deleting them",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoints = "The array of the breakpoint numbers to disable, major.minor (e.g. 2.1) \
+                       disables a single location of a breakpoint with several locations"
+    )
+)]
+pub async fn disable_breakpoints_tool(
+    session_id: String,
+    breakpoints: Vec<String>,
+) -> Result<ToolResponseContent> {
+    GDB_MANAGER.disable_breakpoints(&session_id, breakpoints).await?;
+    Ok(tool_text_content!("Breakpoints disabled".to_string()))
+}
+
+#[tool(
+    name = "set_breakpoint_condition",
+    description = "Change the condition of a breakpoint, it only stops when the condition is true",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoint = "The breakpoint number",
+        condition = "The condition expression, if not provided the breakpoint becomes \
+                     unconditional"
+    )
+)]
+pub async fn set_breakpoint_condition_tool(
+    session_id: String,
+    breakpoint: String,
+    condition: Option<String>,
+) -> Result<ToolResponseContent> {
+    GDB_MANAGER.set_breakpoint_condition(&session_id, &breakpoint, condition.as_deref()).await?;
+    Ok(tool_text_content!("Breakpoint condition set".to_string()))
+}
+
+#[tool(
+    name = "set_breakpoint_ignore_count",
+    description = "Ignore the next hits of a breakpoint",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoint = "The breakpoint number",
+        count = "Number of hits to ignore, 0 stops at the next hit again"
+    )
+)]
+pub async fn set_breakpoint_ignore_count_tool(
+    session_id: String,
+    breakpoint: String,
+    count: PositiveInt,
+) -> Result<ToolResponseContent> {
+    GDB_MANAGER.set_breakpoint_ignore_count(&session_id, &breakpoint, count.0 as usize).await?;
+    Ok(tool_text_content!("Breakpoint ignore count set".to_string()))
+}
+
+#[tool(
+    name = "set_breakpoint_actions",
+    description = "Attach actions to a breakpoint, run every time it is hit, whether a tool \
+                   waits for the program or not: evaluate expressions, capture registers and a \
+                   backtrace, and optionally continue. The snapshots are kept per hit, get them \
+                   with get_breakpoint_snapshots. Without any action, the actions are removed",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoint = "The breakpoint number",
+        expressions = "if provided, the array of expressions to evaluate",
+        registers = "if provided, the array of the numbers of the registers to capture",
+        backtrace_depth = "if provided, the number of frames of the backtrace to capture",
+        auto_continue = "if true, continue after collecting instead of reporting the stop"
+    )
+)]
+pub async fn set_breakpoint_actions_tool(
+    session_id: String,
+    breakpoint: String,
+    expressions: Option<Vec<String>>,
+    registers: Option<Vec<String>>,
+    backtrace_depth: Option<PositiveInt>,
+    auto_continue: Option<bool>,
+) -> Result<ToolResponseContent> {
+    let actions = BreakPointActions {
+        expressions: expressions.unwrap_or_default(),
+        registers: registers.unwrap_or_default(),
+        backtrace_depth: backtrace_depth.map_or(0, |v| v.0 as usize),
+        auto_continue: auto_continue.unwrap_or(false),
+    };
+    GDB_MANAGER.set_breakpoint_actions(&session_id, &breakpoint, actions).await?;
+    Ok(tool_text_content!("Breakpoint actions set".to_string()))
+}
+
+#[tool(
+    name = "get_breakpoint_snapshots",
+    description = "Get what the actions of a breakpoint collected on its last hits, oldest first",
+    params(
+        session_id = "The ID of the GDB session",
+        breakpoint = "The breakpoint number",
+        expression = "if provided, only return the values of this expression",
+        limit = "if provided, the number of hits to return (default 100)"
+    )
+)]
+pub async fn get_breakpoint_snapshots_tool(
+    session_id: String,
+    breakpoint: String,
+    expression: Option<String>,
+    limit: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let mut snapshots = GDB_MANAGER
+        .get_breakpoint_snapshots(&session_id, &breakpoint, limit.map(|v| v.0 as usize))
+        .await?;
+    if let Some(expression) = expression {
+        for snapshot in &mut snapshots {
+            snapshot.values.retain(|value| value.expression == expression);
+            snapshot.registers = None;
+            snapshot.backtrace = None;
+        }
+    }
+    Ok(tool_text_content!(format!("Snapshots: {}", serde_json::to_string(&snapshots)?)))
+}
+
+#[tool(
+    name = "export_breakpoints",
+    description = "Save the breakpoints and watchpoints of a session to a JSON file, to set them \
+                   again with import_breakpoints, e.g. after rebuilding and reflashing the program",
+    params(
+        session_id = "The ID of the GDB session",
+        path = "Path of the JSON file to write"
+    )
+)]
+pub async fn export_breakpoints_tool(
+    session_id: String,
+    path: String,
+) -> Result<ToolResponseContent> {
+    let specs = GDB_MANAGER.export_breakpoints(&session_id, &PathBuf::from(path)).await?;
+    Ok(tool_text_content!(format!("Exported breakpoints: {}", serde_json::to_string(&specs)?)))
+}
+
+#[tool(
+    name = "import_breakpoints",
+    description = "Set the breakpoints and watchpoints saved by export_breakpoints, by the \
+                   location they were set at so they survive rebuilding the program",
+    params(
+        session_id = "The ID of the GDB session",
+        path = "Path of the JSON file written by export_breakpoints"
+    )
+)]
+pub async fn import_breakpoints_tool(
+    session_id: String,
+    path: String,
+) -> Result<ToolResponseContent> {
+    let restored = GDB_MANAGER.import_breakpoints(&session_id, &PathBuf::from(path)).await?;
+    Ok(tool_text_content!(format!("Imported breakpoints: {}", serde_json::to_string(&restored)?)))
+}
+
+#[tool(
+    name = "list_threads",
+    description = "List the threads of the program with their id, target id, name, state, \
+                   core and current frame, and the id of the selected thread",
+    params(session_id = "The ID of the GDB session")
+)]
+pub async fn list_threads_tool(session_id: String) -> Result<ToolResponseContent> {
+    let threads = GDB_MANAGER.list_threads(&session_id).await?;
+    Ok(tool_text_content!(format!("Threads: {}", serde_json::to_string(&threads)?)))
+}
+
+#[tool(
+    name = "select_thread",
+    description = "Select the thread that commands without a thread apply to",
+    params(
+        session_id = "The ID of the GDB session",
+        thread_id = "The ID of the thread, as listed by list_threads"
+    )
+)]
+pub async fn select_thread_tool(
+    session_id: String,
+    thread_id: PositiveInt,
+) -> Result<ToolResponseContent> {
+    let thread = GDB_MANAGER.select_thread(&session_id, thread_id.0 as usize).await?;
+    Ok(tool_text_content!(format!("Selected thread: {}", serde_json::to_string(&thread)?)))
+}
+
 #[tool(
     name = "get_stack_frames",
     description = "Get stack frames in the current GDB session",
-    params(session_id = "The ID of the GDB session")
+    params(
+        session_id = "The ID of the GDB session",
+        thread_id = "if provided, get the frames of this thread instead of the selected one"
+    )
 )]
-pub async fn get_stack_frames_tool(session_id: String) -> Result<ToolResponseContent> {
-    let frames = GDB_MANAGER.get_stack_frames(&session_id).await?;
+pub async fn get_stack_frames_tool(
+    session_id: String,
+    thread_id: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let frames =
+        GDB_MANAGER.get_stack_frames(&session_id, thread_id.map(|v| v.0 as usize)).await?;
     Ok(tool_text_content!(format!("Stack frames: {}", serde_json::to_string(&frames)?)))
 }
 
+#[tool(
+    name = "get_all_backtraces",
+    description = "Get the stack frames of every thread, threads with identical stacks are \
+                   grouped and counted, the most common stack first",
+    params(
+        session_id = "The ID of the GDB session",
+        depth = "if provided, only compare and return the innermost frames of each thread"
+    )
+)]
+pub async fn get_all_backtraces_tool(
+    session_id: String,
+    depth: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let backtraces =
+        GDB_MANAGER.get_all_backtraces(&session_id, depth.map(|v| v.0 as usize)).await?;
+    Ok(tool_text_content!(format!("Backtraces: {}", serde_json::to_string(&backtraces)?)))
+}
+
 #[tool(
     name = "get_local_variables",
     description = "Get local variables in the current stack frame",
     params(
         session_id = "The ID of the GDB session",
+        thread_id = "if provided, get the variables of this thread instead of the selected one",
         frame_id = "The ID of the stack frame, defaults to 0, the topest frame"
     )
 )]
 pub async fn get_local_variables_tool(
     session_id: String,
+    thread_id: Option<PositiveInt>,
     frame_id: Option<PositiveInt>,
 ) -> Result<ToolResponseContent> {
-    let variables = GDB_MANAGER.get_local_variables(&session_id, frame_id.map(|v| v.0 as usize)).await?;
+    let variables = GDB_MANAGER
+        .get_local_variables(
+            &session_id,
+            thread_id.map(|v| v.0 as usize),
+            frame_id.map(|v| v.0 as usize),
+        )
+        .await?;
     Ok(tool_text_content!(format!("Local variables: {}", serde_json::to_string(&variables)?)))
 }
 
+#[tool(
+    name = "evaluate_expression",
+    description = "Evaluate an expression in the context of the program, e.g. a variable, \
+                   a struct field or a function call, and return its value and type",
+    params(
+        session_id = "The ID of the GDB session",
+        expression = "The expression to evaluate, in the language of the program",
+        thread_id = "if provided, evaluate in this thread instead of the selected one",
+        frame_id = "if provided, evaluate in this frame of the thread, 0 is the innermost frame",
+        format = "if provided, format of the value: natural (default), hex, decimal, octal, \
+                  binary or zero-hexadecimal"
+    )
+)]
+pub async fn evaluate_expression_tool(
+    session_id: String,
+    expression: String,
+    thread_id: Option<PositiveInt>,
+    frame_id: Option<PositiveInt>,
+    format: Option<String>,
+) -> Result<ToolResponseContent> {
+    let format =
+        format.map(|f| f.parse::<ValueFormat>()).transpose().map_err(AppError::InvalidArgument)?;
+    let value = GDB_MANAGER
+        .evaluate_expression(
+            &session_id,
+            &expression,
+            thread_id.map(|v| v.0 as usize),
+            frame_id.map(|v| v.0 as usize),
+            format,
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Value: {}", serde_json::to_string(&value)?)))
+}
+
 #[tool(
     name = "get_registers",
     description = "Get registers in the current GDB session",
     params(
         session_id = "The ID of the GDB session",
+        thread_id = "if provided, get the registers of this thread instead of the selected one",
         reg_list = "The array of the registers to get",
     )
 )]
 pub async fn get_registers_tool(
     session_id: String,
+    thread_id: Option<PositiveInt>,
     reg_list: Option<Vec<String>>,
 ) -> Result<ToolResponseContent> {
-    let registers = GDB_MANAGER.get_registers(&session_id, reg_list).await?;
+    let registers =
+        GDB_MANAGER.get_registers(&session_id, thread_id.map(|v| v.0 as usize), reg_list).await?;
     Ok(tool_text_content!(format!("Registers: {}", serde_json::to_string(&registers)?)))
 }
 
@@ -312,30 +849,398 @@ pub async fn read_memory_tool(
 
 #[tool(
     name = "continue_execution",
-    description = "Continue program execution",
-    params(session_id = "The ID of the GDB session")
+    description = "Continue program execution, optionally waiting for the program to stop. \
+                   The stop event reports the reason, breakpoint number, signal, \
+                   thread ID and frame",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, run backwards through the execution recorded since \
+                   start_recording, the stop also reports the instruction stopped at",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
 )]
-pub async fn continue_execution_tool(session_id: String) -> Result<ToolResponseContent> {
-    let ret = GDB_MANAGER.continue_execution(&session_id).await?;
-    Ok(tool_text_content!(format!("Continued execution: {}", ret)))
+pub async fn continue_execution_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let ret = GDB_MANAGER
+        .continue_execution(
+            &session_id,
+            reverse.unwrap_or(false),
+            wait.unwrap_or(false),
+            timeout.map(|v| v.0 as u64),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Continued execution: {}", serde_json::to_string(&ret)?)))
+}
+
+/// How often to repeat a step, none for a single step
+fn step_repeat(
+    count: Option<PositiveInt>,
+    until: Option<String>,
+    until_change: Option<String>,
+    max_steps: Option<PositiveInt>,
+) -> Result<Option<StepRepeat>, AppError> {
+    let until = match (until, until_change.as_deref()) {
+        (None, None) => None,
+        (Some(expression), None) => Some(StepCondition::Expression(expression)),
+        (None, Some("line")) => Some(StepCondition::LineChanged),
+        (None, Some("function")) => Some(StepCondition::FunctionChanged),
+        (None, Some(other)) => {
+            return Err(AppError::InvalidArgument(format!(
+                "invalid until_change {}, expected line or function",
+                other
+            )));
+        }
+        (Some(_), Some(_)) => {
+            return Err(AppError::InvalidArgument("set either until or until_change".to_string()));
+        }
+    };
+    if count.is_none() && until.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(StepRepeat {
+        count: count.map(|v| v.0 as usize),
+        until,
+        max_steps: max_steps.map(|v| v.0 as usize),
+    }))
 }
 
 #[tool(
     name = "step_execution",
-    description = "Step into next line",
-    params(session_id = "The ID of the GDB session")
+    description = "Step into next line, optionally repeated or waiting for the program to stop. \
+                   The stop event reports the reason, breakpoint number, signal, \
+                   thread ID and frame",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, run backwards through the execution recorded since \
+                   start_recording, the stop also reports the instruction stopped at",
+        count = "if provided, the number of steps to make, waiting for each. The steps end \
+                 early if the program stops for another reason, e.g. at a breakpoint",
+        until = "if provided, step until this expression is true, evaluated after every step",
+        until_change = "if provided, step until the source line or the function is another \
+                        one: line or function",
+        max_steps = "if provided, the most steps made to reach until or until_change, defaults \
+                     to GDB_MAX_STEPS",
+        wait = "if true, wait until the program stops and return the stop event, repeated \
+                steps always wait",
+        timeout = "if provided, seconds to wait for the program to stop after every step, \
+                   defaults to the command timeout"
+    )
 )]
-pub async fn step_execution_tool(session_id: String) -> Result<ToolResponseContent> {
-    let ret = GDB_MANAGER.step_execution(&session_id).await?;
-    Ok(tool_text_content!(format!("Stepped into next line: {}", ret)))
+#[allow(clippy::too_many_arguments)]
+pub async fn step_execution_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    count: Option<PositiveInt>,
+    until: Option<String>,
+    until_change: Option<String>,
+    max_steps: Option<PositiveInt>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let reverse = reverse.unwrap_or(false);
+    let timeout = timeout.map(|v| v.0 as u64);
+    if let Some(repeat) = step_repeat(count, until, until_change, max_steps)? {
+        let ret = GDB_MANAGER.repeat_step(&session_id, false, reverse, &repeat, timeout).await?;
+        let text = format!("Stepped {} times: {}", ret.steps, serde_json::to_string(&ret)?);
+        return Ok(tool_text_content!(text));
+    }
+    let ret = GDB_MANAGER
+        .step_execution(&session_id, reverse, wait.unwrap_or(false), timeout)
+        .await?;
+    Ok(tool_text_content!(format!("Stepped into next line: {}", serde_json::to_string(&ret)?)))
 }
 
 #[tool(
     name = "next_execution",
-    description = "Step over next line",
+    description = "Step over next line, optionally repeated or waiting for the program to stop. \
+                   The stop event reports the reason, breakpoint number, signal, \
+                   thread ID and frame",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, run backwards through the execution recorded since \
+                   start_recording, the stop also reports the instruction stopped at",
+        count = "if provided, the number of steps to make, waiting for each. The steps end \
+                 early if the program stops for another reason, e.g. at a breakpoint",
+        until = "if provided, step until this expression is true, evaluated after every step",
+        until_change = "if provided, step until the source line or the function is another \
+                        one: line or function",
+        max_steps = "if provided, the most steps made to reach until or until_change, defaults \
+                     to GDB_MAX_STEPS",
+        wait = "if true, wait until the program stops and return the stop event, repeated \
+                steps always wait",
+        timeout = "if provided, seconds to wait for the program to stop after every step, \
+                   defaults to the command timeout"
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn next_execution_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    count: Option<PositiveInt>,
+    until: Option<String>,
+    until_change: Option<String>,
+    max_steps: Option<PositiveInt>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let reverse = reverse.unwrap_or(false);
+    let timeout = timeout.map(|v| v.0 as u64);
+    if let Some(repeat) = step_repeat(count, until, until_change, max_steps)? {
+        let ret = GDB_MANAGER.repeat_step(&session_id, true, reverse, &repeat, timeout).await?;
+        let text = format!("Stepped {} times: {}", ret.steps, serde_json::to_string(&ret)?);
+        return Ok(tool_text_content!(text));
+    }
+    let ret = GDB_MANAGER
+        .next_execution(&session_id, reverse, wait.unwrap_or(false), timeout)
+        .await?;
+    Ok(tool_text_content!(format!("Stepped over next line: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "finish_execution",
+    description = "Run until the selected function returns, optionally waiting for the program \
+                   to stop. The stop event reports the returned value as return_value",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, run backwards through the execution recorded since \
+                   start_recording, the stop also reports the instruction stopped at",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn finish_execution_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let ret = GDB_MANAGER
+        .finish_execution(
+            &session_id,
+            reverse.unwrap_or(false),
+            wait.unwrap_or(false),
+            timeout.map(|v| v.0 as u64),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Finished function: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "step_instruction",
+    description = "Step one machine instruction, into calls, optionally waiting for the program \
+                   to stop. The stop event reports the instruction stepped to",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, step backwards through the execution recorded since start_recording",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn step_instruction_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let ret = GDB_MANAGER
+        .step_instruction(
+            &session_id,
+            reverse.unwrap_or(false),
+            wait.unwrap_or(false),
+            timeout.map(|v| v.0 as u64),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Stepped instruction: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "next_instruction",
+    description = "Step one machine instruction, over calls, optionally waiting for the program \
+                   to stop. The stop event reports the instruction stepped to",
+    params(
+        session_id = "The ID of the GDB session",
+        reverse = "if true, step backwards through the execution recorded since start_recording",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn next_instruction_tool(
+    session_id: String,
+    reverse: Option<bool>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let ret = GDB_MANAGER
+        .next_instruction(
+            &session_id,
+            reverse.unwrap_or(false),
+            wait.unwrap_or(false),
+            timeout.map(|v| v.0 as u64),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Stepped over instruction: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "start_recording",
+    description = "Start recording the execution of the running program (record full), so that \
+                   continue, step, next, finish and the instruction steps can run backwards. \
+                   Recording slows the program down a lot",
     params(session_id = "The ID of the GDB session")
 )]
-pub async fn next_execution_tool(session_id: String) -> Result<ToolResponseContent> {
-    let ret = GDB_MANAGER.next_execution(&session_id).await?;
-    Ok(tool_text_content!(format!("Stepped over next line: {}", ret)))
+pub async fn start_recording_tool(session_id: String) -> Result<ToolResponseContent> {
+    GDB_MANAGER.start_recording(&session_id).await?;
+    Ok(tool_text_content!("Recording started".to_string()))
+}
+
+#[tool(
+    name = "stop_recording",
+    description = "Stop recording the execution of the program, the recorded history is dropped",
+    params(session_id = "The ID of the GDB session")
+)]
+pub async fn stop_recording_tool(session_id: String) -> Result<ToolResponseContent> {
+    GDB_MANAGER.stop_recording(&session_id).await?;
+    Ok(tool_text_content!("Recording stopped".to_string()))
+}
+
+#[tool(
+    name = "until_execution",
+    description = "Run until a line past the current one, e.g. to leave a loop, or until a \
+                   location if given, optionally waiting for the program to stop. It also stops \
+                   when the selected function returns",
+    params(
+        session_id = "The ID of the GDB session",
+        file = "if provided, source file path, with line for a source line, or with function \
+                to pick the function of this file",
+        line = "if provided, line number in file",
+        function = "if provided, function name",
+        address = "if provided, instruction address, as hexadecimal literal, e.g. 0x401136",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn until_execution_tool(
+    session_id: String,
+    file: Option<String>,
+    line: Option<PositiveInt>,
+    function: Option<String>,
+    address: Option<String>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let file = file.map(PathBuf::from);
+    let location = match (&file, &line, &function, &address) {
+        (None, None, None, None) => None,
+        _ => Some(breakpoint_location(
+            file.as_deref(),
+            line,
+            function.as_deref(),
+            address.as_deref(),
+        )?),
+    };
+    let ret = GDB_MANAGER
+        .until_execution(&session_id, location, wait.unwrap_or(false), timeout.map(|v| v.0 as u64))
+        .await?;
+    Ok(tool_text_content!(format!("Ran until: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "advance_execution",
+    description = "Run until a source line (file and line), a function or an address, \
+                   optionally waiting for the program to stop. It also stops when the selected \
+                   function returns",
+    params(
+        session_id = "The ID of the GDB session",
+        file = "Source file path, with line for a source line, or with function to pick the \
+                function of this file",
+        line = "Line number in file",
+        function = "Function name",
+        address = "Instruction address, as hexadecimal literal, e.g. 0x401136",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn advance_execution_tool(
+    session_id: String,
+    file: Option<String>,
+    line: Option<PositiveInt>,
+    function: Option<String>,
+    address: Option<String>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let file = file.map(PathBuf::from);
+    let location =
+        breakpoint_location(file.as_deref(), line, function.as_deref(), address.as_deref())?;
+    let ret = GDB_MANAGER
+        .advance_execution(
+            &session_id,
+            location,
+            wait.unwrap_or(false),
+            timeout.map(|v| v.0 as u64),
+        )
+        .await?;
+    Ok(tool_text_content!(format!("Advanced: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "jump_execution",
+    description = "Resume execution at a source line (file and line), a function or an address, \
+                   skipping or repeating code, optionally waiting for the program to stop",
+    params(
+        session_id = "The ID of the GDB session",
+        file = "Source file path, with line for a source line, or with function to pick the \
+                function of this file",
+        line = "Line number in file",
+        function = "Function name",
+        address = "Instruction address, as hexadecimal literal, e.g. 0x401136",
+        wait = "if true, wait until the program stops and return the stop event",
+        timeout = "if provided, seconds to wait for the program to stop, \
+                   defaults to the command timeout"
+    )
+)]
+pub async fn jump_execution_tool(
+    session_id: String,
+    file: Option<String>,
+    line: Option<PositiveInt>,
+    function: Option<String>,
+    address: Option<String>,
+    wait: Option<bool>,
+    timeout: Option<PositiveInt>,
+) -> Result<ToolResponseContent> {
+    let file = file.map(PathBuf::from);
+    let location =
+        breakpoint_location(file.as_deref(), line, function.as_deref(), address.as_deref())?;
+    let ret = GDB_MANAGER
+        .jump_execution(&session_id, location, wait.unwrap_or(false), timeout.map(|v| v.0 as u64))
+        .await?;
+    Ok(tool_text_content!(format!("Jumped: {}", serde_json::to_string(&ret)?)))
+}
+
+#[tool(
+    name = "return_from_function",
+    description = "Return from the selected function right away without running the rest of \
+                   it, the program doesn't resume. Returns the frame returned to",
+    params(
+        session_id = "The ID of the GDB session",
+        value = "if provided, the expression whose value the function returns"
+    )
+)]
+pub async fn return_from_function_tool(
+    session_id: String,
+    value: Option<String>,
+) -> Result<ToolResponseContent> {
+    let frame = GDB_MANAGER.return_from_function(&session_id, value.as_deref()).await?;
+    Ok(tool_text_content!(format!("Returned to: {}", serde_json::to_string(&frame)?)))
 }